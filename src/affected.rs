@@ -0,0 +1,206 @@
+use crate::{
+    commands::{find_files, git_changed_files, git_toplevel, path_from_bytes},
+    config::AffectedArgs,
+    dependencies::Dependencies,
+    errors::{ProjectFinderError, Result},
+    finder::ProjectFinder,
+    manifest::{DEPENDENCY_MANIFESTS, LocalDependency, local_dependencies, package_name},
+    project::Project,
+};
+use std::{
+    collections::{BTreeSet, HashMap},
+    path::{Path, PathBuf, absolute},
+};
+use tokio::{
+    fs::canonicalize,
+    io::{AsyncBufReadExt, BufReader, stdin},
+};
+use tracing::{debug, warn};
+
+/// Find the projects affected by a set of changed files.
+///
+/// Each changed file is mapped to its project root. When requested, projects that
+/// depend on an affected project through a local path dependency are added as well,
+/// transitively.
+///
+/// # Returns
+///
+/// The affected projects, sorted by root and deduplicated.
+pub async fn find_affected(
+    finder: &ProjectFinder,
+    deps: &Dependencies,
+    args: &AffectedArgs,
+    max_depth: usize,
+) -> Result<Vec<Project>> {
+    let files = changed_files(deps, args).await?;
+
+    let mut affected = BTreeSet::new();
+    for file in &files {
+        if let Some(root) = resolve_root(finder, file, args.members).await? {
+            debug!("{} belongs to {}", file.display(), root.display());
+            affected.insert(root);
+        } else {
+            debug!("{} does not belong to any project", file.display());
+        }
+    }
+
+    if args.dependents && !affected.is_empty() {
+        add_dependents(finder, deps, args, max_depth, &mut affected).await?;
+    }
+
+    let mut projects = Vec::with_capacity(affected.len());
+    for root in affected {
+        projects.push(finder.describe_project(&root).await?);
+    }
+
+    Ok(projects)
+}
+
+/// Collect the changed files from the command line, `git diff` or stdin.
+///
+/// Relative paths read from stdin are usually `git diff --name-only` output, so they are
+/// taken relative to the top of the Git repository containing the current directory, or
+/// to the current directory outside of one.
+async fn changed_files(deps: &Dependencies, args: &AffectedArgs) -> Result<Vec<PathBuf>> {
+    if let Some(rev) = &args.since {
+        return git_changed_files(deps, Path::new("."), rev).await;
+    }
+
+    if !args.files.is_empty() {
+        return Ok(args.files.clone());
+    }
+
+    // Read raw bytes so file names that are not valid UTF-8 are kept intact. Only the
+    // line ending is stripped, as other whitespace can be part of a file name.
+    let mut files = Vec::new();
+    let mut reader = BufReader::new(stdin());
+    let mut line = Vec::new();
+    while reader.read_until(b'\n', &mut line).await? > 0 {
+        let name = line.strip_suffix(b"\n").unwrap_or(&line);
+        let name = name.strip_suffix(b"\r").unwrap_or(name);
+        if !name.is_empty() {
+            files.push(path_from_bytes(name.to_vec()));
+        }
+        line.clear();
+    }
+
+    if files.iter().any(|file| file.is_relative()) {
+        match git_toplevel(deps, Path::new(".")).await {
+            Ok(toplevel) => {
+                for file in files.iter_mut().filter(|file| file.is_relative()) {
+                    *file = toplevel.join(&*file);
+                }
+            }
+            Err(e) => debug!("Taking changed files relative to the current directory: {e}"),
+        }
+    }
+
+    Ok(files)
+}
+
+/// Resolve the project root of a path and canonicalize it, so roots reached through
+/// different relative paths compare equal.
+async fn resolve_root(
+    finder: &ProjectFinder,
+    path: &Path,
+    members: bool,
+) -> Result<Option<PathBuf>> {
+    let path = absolute(path)?;
+    match finder.resolve_root(&path, members).await? {
+        Some(root) => Ok(Some(canonicalize(root).await?)),
+        None => Ok(None),
+    }
+}
+
+/// Extend `affected` with every project that depends on one of its members, directly
+/// or through other dependents.
+async fn add_dependents(
+    finder: &ProjectFinder,
+    deps: &Dependencies,
+    args: &AffectedArgs,
+    max_depth: usize,
+    affected: &mut BTreeSet<PathBuf>,
+) -> Result<()> {
+    let mut manifests = Vec::new();
+    for dir in &args.search_paths {
+        if !dir.is_dir() {
+            return Err(ProjectFinderError::PathNotFound(dir.clone()));
+        }
+
+        let found = find_files(deps, dir, &DEPENDENCY_MANIFESTS, max_depth).await?;
+        manifests.extend(found.into_values().flatten().filter(|manifest| {
+            !manifest
+                .components()
+                .any(|component| component.as_os_str() == "node_modules")
+        }));
+    }
+
+    // Map npm package names to their directories to resolve `workspace:` dependencies.
+    let mut packages = HashMap::new();
+    for manifest in manifests
+        .iter()
+        .filter(|manifest| manifest.ends_with("package.json"))
+    {
+        match package_name(manifest).await {
+            Ok(Some(name)) => {
+                if let Some(dir) = manifest.parent() {
+                    packages.insert(name, dir.to_path_buf());
+                }
+            }
+            Ok(None) => {}
+            Err(e) => warn!("Skipping {}: {e}", manifest.display()),
+        }
+    }
+
+    // Build the dependency edges between project roots.
+    let mut edges = Vec::new();
+    for manifest in &manifests {
+        let dependencies = match local_dependencies(manifest).await {
+            Ok(dependencies) => dependencies,
+            Err(e) => {
+                warn!("Skipping {}: {e}", manifest.display());
+                continue;
+            }
+        };
+
+        let Some(owner) = resolve_root(finder, manifest, args.members).await? else {
+            continue;
+        };
+
+        for dependency in dependencies {
+            let dir = match dependency {
+                LocalDependency::Path(path) => path,
+                LocalDependency::Workspace(name) => match packages.get(&name) {
+                    Some(dir) => dir.clone(),
+                    None => continue,
+                },
+            };
+
+            if !dir.exists() {
+                debug!("Ignoring missing dependency {}", dir.display());
+                continue;
+            }
+
+            if let Some(target) = resolve_root(finder, &dir, args.members).await?
+                && target != owner
+            {
+                edges.push((owner.clone(), target));
+            }
+        }
+    }
+
+    // Propagate until no new dependents are found.
+    loop {
+        let before = affected.len();
+        for (owner, target) in &edges {
+            if affected.contains(target) {
+                affected.insert(owner.clone());
+            }
+        }
+        if affected.len() == before {
+            break;
+        }
+    }
+
+    Ok(())
+}
@@ -1,10 +1,12 @@
 use crate::{
     dependencies::Dependencies,
     errors::{ProjectFinderError, Result},
+    git::is_bare,
+    vcs::{self, is_vcs_root},
 };
 use regex::{Regex, escape};
 use std::{
-    collections::HashMap,
+    collections::{BTreeSet, HashMap},
     fmt::Display,
     path::{Path, PathBuf},
     process::Stdio,
@@ -21,11 +23,36 @@ fn wrap_command_error<E: Display>(action: &str, err: E) -> ProjectFinderError {
     ProjectFinderError::CommandExecutionFailed(format!("{action}: {err}"))
 }
 
+/// Convert raw bytes from a command's output into a path without any lossy conversion.
+///
+/// On Unix paths are arbitrary bytes, so they are taken as is. Elsewhere the bytes are
+/// expected to be UTF-8 and invalid sequences are replaced.
+pub fn path_from_bytes(bytes: Vec<u8>) -> PathBuf {
+    #[cfg(unix)]
+    {
+        use std::os::unix::ffi::OsStringExt;
+        PathBuf::from(std::ffi::OsString::from_vec(bytes))
+    }
+    #[cfg(not(unix))]
+    {
+        PathBuf::from(String::from_utf8_lossy(&bytes).into_owned())
+    }
+}
+
+/// Split NUL-delimited command output into paths.
+fn split_paths(output: &[u8]) -> impl Iterator<Item = PathBuf> + '_ {
+    output
+        .split(|&byte| byte == 0)
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| path_from_bytes(entry.to_vec()))
+}
+
 /// Run the `fd` command to find files matching one or more literal patterns.
 ///
 /// The function builds a combined regex pattern from the list of patterns, runs the
 /// command asynchronously, and collects matching file paths in a map keyed by the literal
-/// file name.
+/// file name. Output is read as NUL-delimited raw bytes, so paths that are not valid
+/// UTF-8 are kept intact instead of aborting the search.
 ///
 /// # Arguments
 ///
@@ -59,6 +86,7 @@ pub async fn find_files(
         .arg("--no-ignore-vcs")
         .arg("--type")
         .arg("f")
+        .arg("--print0")
         .arg("--max-depth")
         .arg(max_depth.to_string())
         .arg(&combined_patterns)
@@ -75,8 +103,7 @@ pub async fn find_files(
     let stdout = child.stdout.take().ok_or_else(|| {
         ProjectFinderError::CommandExecutionFailed("Failed to capture stdout".into())
     })?;
-    let reader = BufReader::new(stdout);
-    let mut lines = reader.lines();
+    let mut reader = BufReader::new(stdout);
 
     // Prepare the results map with an empty vector for each pattern.
     let mut results = patterns
@@ -84,19 +111,29 @@ pub async fn find_files(
         .map(|pattern| ((*pattern).to_string(), Vec::new()))
         .collect::<HashMap<_, _>>();
 
-    // Stream and process output as lines arrive.
-    while let Some(line) = lines
-        .next_line()
-        .await
-        .map_err(|e| wrap_command_error("Failed to read stdout", e))?
-    {
-        let path = PathBuf::from(line);
+    // Stream and process output as entries arrive.
+    let mut entry = Vec::new();
+    loop {
+        entry.clear();
+        let read = reader
+            .read_until(0, &mut entry)
+            .await
+            .map_err(|e| wrap_command_error("Failed to read stdout", e))?;
+        if read == 0 {
+            break;
+        }
+        if entry.last() == Some(&0) {
+            entry.pop();
+        }
+
+        let path = path_from_bytes(std::mem::take(&mut entry));
         // For each found file, only add it if its file name exactly matches one
         // of the provided patterns.
-        if let Some(file_name) = path.file_name().and_then(|f| f.to_str()) {
-            if let Some(entries) = results.get_mut(file_name) {
-                entries.push(path);
-            }
+        let pattern = path
+            .file_name()
+            .and_then(|file_name| patterns.iter().find(|pattern| file_name == **pattern));
+        if let Some(entries) = pattern.and_then(|pattern| results.get_mut(*pattern)) {
+            entries.push(path);
         }
     }
 
@@ -112,41 +149,104 @@ pub async fn find_files(
     Ok(results)
 }
 
-/// Find Git repositories by searching for '.git' directories.
+/// Find repositories by searching for the entries marking the root of a working copy.
+///
+/// This function invokes the `fd` command with a pattern matching the markers of every
+/// supported version control system, such as '.git', '.hg' or '.jj'. `.git` may be a
+/// directory or, for linked worktrees and submodule checkouts, a file pointing to the Git
+/// directory. For each entry that marks a working copy, it returns the parent path (the
+/// repository root), once per root even for colocated repositories.
+///
+/// # Arguments
+///
+/// - `deps`: Dependencies containing the path to the `fd` binary.
+/// - `dir`: The directory to search for repositories.
+/// - `max_depth`: The maximum directory depth to search.
+///
+/// # Returns
 ///
-/// This function invokes the `fd` command with the pattern '^.git$'. For each
-/// found directory, it returns the parent path (the Git repository root).
+/// A vector of paths representing the roots of repositories.
+pub async fn find_repos(deps: &Dependencies, dir: &Path, max_depth: usize) -> Result<Vec<PathBuf>> {
+    let pattern = format!(
+        "^({})$",
+        vcs::marker_names()
+            .map(escape)
+            .collect::<Vec<_>>()
+            .join("|")
+    );
+
+    let mut cmd = Command::new(&deps.fd_path);
+    cmd.arg("--hidden")
+        .arg("--type")
+        .arg("d")
+        .arg("--type")
+        .arg("f")
+        .arg("--print0")
+        .arg("--max-depth")
+        .arg(max_depth.to_string())
+        .arg(&pattern)
+        .arg(dir)
+        .stdout(Stdio::piped());
+
+    debug!("Finding repos in {}", dir.display());
+
+    let output = cmd
+        .output()
+        .await
+        .map_err(|e| wrap_command_error("Failed to find repositories", e))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        warn!("fd command failed: {stderr}");
+        return Ok(Vec::new());
+    }
+
+    // For each found marker, return its parent directory.
+    let mut paths = BTreeSet::new();
+    for repo in split_paths(&output.stdout).filter_map(|path| path.parent().map(Path::to_path_buf))
+    {
+        if is_vcs_root(&repo).await {
+            paths.insert(repo);
+        }
+    }
+
+    Ok(paths.into_iter().collect())
+}
+
+/// Find bare Git repositories by searching for their `HEAD` files.
 ///
 /// # Arguments
 ///
 /// - `deps`: Dependencies containing the path to the `fd` binary.
-/// - `dir`: The directory to search for Git repositories.
+/// - `dir`: The directory to search for bare repositories.
 /// - `max_depth`: The maximum directory depth to search.
 ///
 /// # Returns
 ///
-/// A vector of paths representing the roots of Git repositories.
-pub async fn find_git_repos(
+/// A vector of paths to bare repositories, see [`crate::git::is_bare`].
+pub async fn find_bare_repos(
     deps: &Dependencies,
     dir: &Path,
     max_depth: usize,
 ) -> Result<Vec<PathBuf>> {
     let mut cmd = Command::new(&deps.fd_path);
     cmd.arg("--hidden")
+        .arg("--no-ignore-vcs")
         .arg("--type")
-        .arg("d")
+        .arg("f")
+        .arg("--print0")
         .arg("--max-depth")
         .arg(max_depth.to_string())
-        .arg("^.git$")
+        .arg("^HEAD$")
         .arg(dir)
         .stdout(Stdio::piped());
 
-    debug!("Finding git repos in {}", dir.display());
+    debug!("Finding bare repos in {}", dir.display());
 
     let output = cmd
         .output()
         .await
-        .map_err(|e| wrap_command_error("Failed to find git repositories", e))?;
+        .map_err(|e| wrap_command_error("Failed to find bare repositories", e))?;
 
     if !output.status.success() {
         let stderr = String::from_utf8_lossy(&output.stderr);
@@ -154,20 +254,58 @@ pub async fn find_git_repos(
         return Ok(Vec::new());
     }
 
-    let stdout = String::from_utf8(output.stdout).map_err(ProjectFinderError::Utf8Error)?;
-
-    // For each found '.git' directory, return its parent directory.
-    let paths = stdout
-        .lines()
-        .filter_map(|line| {
-            let path = PathBuf::from(line);
-            path.parent().map(std::path::Path::to_path_buf)
-        })
-        .collect();
+    let mut paths = Vec::new();
+    for repo in split_paths(&output.stdout).filter_map(|path| path.parent().map(Path::to_path_buf))
+    {
+        if is_bare(&repo).await {
+            paths.push(repo);
+        }
+    }
 
     Ok(paths)
 }
 
+/// Find the directories below a directory, leaving out the internals of repositories.
+///
+/// # Arguments
+///
+/// - `deps`: Dependencies containing the path to the `fd` binary.
+/// - `dir`: The directory to search.
+/// - `max_depth`: The maximum directory depth to search.
+///
+/// # Returns
+///
+/// A vector of paths to the directories found, without `dir` itself.
+pub async fn find_dirs(deps: &Dependencies, dir: &Path, max_depth: usize) -> Result<Vec<PathBuf>> {
+    let mut cmd = Command::new(&deps.fd_path);
+    cmd.arg("--hidden")
+        .arg("--no-ignore-vcs")
+        .arg("--type")
+        .arg("d")
+        .arg("--print0")
+        .arg("--max-depth")
+        .arg(max_depth.to_string());
+    for marker in vcs::marker_names() {
+        cmd.arg("--exclude").arg(marker);
+    }
+    cmd.arg(".").arg(dir).stdout(Stdio::piped());
+
+    debug!("Finding directories in {}", dir.display());
+
+    let output = cmd
+        .output()
+        .await
+        .map_err(|e| wrap_command_error("Failed to find directories", e))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        warn!("fd command failed: {stderr}");
+        return Ok(Vec::new());
+    }
+
+    Ok(split_paths(&output.stdout).collect())
+}
+
 /// Read a file into memory and check if it contains any match of the provided regex.
 ///
 /// # Arguments
@@ -191,3 +329,201 @@ pub async fn grep_file_in_memory(file: &Path, pattern: &str) -> Result<bool> {
 
     Ok(re.is_match(&contents))
 }
+
+/// Run a `git` command in the given directory and return its raw standard output.
+async fn git_output(git: &str, dir: &Path, args: &[&str]) -> Result<Vec<u8>> {
+    let output = Command::new(git)
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .output()
+        .await
+        .map_err(|e| wrap_command_error("Failed to run git", e))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(ProjectFinderError::CommandExecutionFailed(format!(
+            "git {} failed: {}",
+            args.join(" "),
+            stderr.trim()
+        )));
+    }
+
+    Ok(output.stdout)
+}
+
+/// Find the top-level directory of the Git repository containing a directory.
+///
+/// # Arguments
+///
+/// - `deps`: Dependencies holding the path to the `git` binary.
+/// - `dir`: A directory inside the Git repository.
+pub async fn git_toplevel(deps: &Dependencies, dir: &Path) -> Result<PathBuf> {
+    let mut toplevel = git_output(deps.git()?, dir, &["rev-parse", "--show-toplevel"]).await?;
+    if toplevel.last() == Some(&b'\n') {
+        toplevel.pop();
+    }
+
+    Ok(path_from_bytes(toplevel))
+}
+
+/// List the files changed since the given revision using `git diff --name-only`.
+///
+/// # Arguments
+///
+/// - `deps`: Dependencies holding the path to the `git` binary.
+/// - `dir`: A directory inside the Git repository.
+/// - `rev`: The revision (or revision range) to diff against.
+///
+/// # Returns
+///
+/// The changed files, joined onto the repository's top-level directory.
+pub async fn git_changed_files(deps: &Dependencies, dir: &Path, rev: &str) -> Result<Vec<PathBuf>> {
+    let toplevel = git_toplevel(deps, dir).await?;
+    let stdout = git_output(deps.git()?, dir, &["diff", "--name-only", "-z", rev]).await?;
+
+    Ok(split_paths(&stdout)
+        .map(|path| toplevel.join(path))
+        .collect())
+}
+
+/// Run `git status` in machine-readable form.
+///
+/// The output of `git status --porcelain=v2 --branch -z` is returned unparsed, see
+/// [`crate::git::parse_status`].
+///
+/// # Arguments
+///
+/// - `deps`: Dependencies holding the path to the `git` binary.
+/// - `dir`: The root of the working tree.
+pub async fn git_status(deps: &Dependencies, dir: &Path) -> Result<Vec<u8>> {
+    git_output(
+        deps.git()?,
+        dir,
+        &["status", "--porcelain=v2", "--branch", "-z"],
+    )
+    .await
+}
+
+/// Count the commits of each local branch that are not on any remote-tracking branch.
+///
+/// Only local references are consulted, nothing is fetched, so the result reflects the
+/// remote-tracking branches as of the last fetch. Two `git` commands are run however
+/// many branches there are: one lists the branches and one the unpushed commits of all
+/// of them, which are then counted per branch.
+///
+/// # Arguments
+///
+/// - `deps`: Dependencies holding the path to the `git` binary.
+/// - `dir`: The root of the repository.
+///
+/// # Returns
+///
+/// The name and number of unpushed commits of every local branch that has any.
+pub async fn git_unpushed_branches(
+    deps: &Dependencies,
+    dir: &Path,
+) -> Result<Vec<(String, usize)>> {
+    let git = deps.git()?;
+    let refs = git_output(
+        git,
+        dir,
+        &[
+            "for-each-ref",
+            "--format=%(objectname) %(refname:lstrip=2)",
+            "refs/heads",
+        ],
+    )
+    .await?;
+    let unpushed = git_output(
+        git,
+        dir,
+        &["rev-list", "--parents", "--branches", "--not", "--remotes"],
+    )
+    .await?;
+
+    // Each line holds an unpushed commit followed by its parents.
+    let unpushed = String::from_utf8_lossy(&unpushed);
+    let parents = unpushed
+        .lines()
+        .filter_map(|line| {
+            let mut hashes = line.split_whitespace();
+            Some((hashes.next()?, hashes.collect::<Vec<_>>()))
+        })
+        .collect::<HashMap<_, _>>();
+
+    let mut branches = Vec::new();
+    for line in String::from_utf8_lossy(&refs).lines() {
+        let Some((tip, branch)) = line.split_once(' ') else {
+            continue;
+        };
+
+        let count = count_reachable(tip, &parents);
+        if count > 0 {
+            branches.push((branch.to_string(), count));
+        }
+    }
+
+    Ok(branches)
+}
+
+/// Count the commits of a graph that are reachable from `tip`, following the parents
+/// only as long as they are part of the graph.
+fn count_reachable(tip: &str, parents: &HashMap<&str, Vec<&str>>) -> usize {
+    let mut seen = BTreeSet::new();
+    let mut pending = vec![tip];
+    while let Some(commit) = pending.pop() {
+        if let Some(commit_parents) = parents.get(commit)
+            && seen.insert(commit)
+        {
+            pending.extend(commit_parents);
+        }
+    }
+
+    seen.len()
+}
+
+/// List the root commits reachable from `HEAD`.
+///
+/// # Arguments
+///
+/// - `deps`: Dependencies holding the path to the `git` binary.
+/// - `dir`: The root of the repository.
+///
+/// # Returns
+///
+/// The full hashes of the commits without parents, sorted.
+pub async fn git_root_commits(deps: &Dependencies, dir: &Path) -> Result<Vec<String>> {
+    let stdout = git_output(deps.git()?, dir, &["rev-list", "--max-parents=0", "HEAD"]).await?;
+
+    let mut roots = String::from_utf8_lossy(&stdout)
+        .lines()
+        .map(str::to_string)
+        .collect::<Vec<_>>();
+    roots.sort();
+    Ok(roots)
+}
+
+/// Read the committer date of the commit `HEAD` points to.
+///
+/// # Arguments
+///
+/// - `deps`: Dependencies holding the path to the `git` binary.
+/// - `dir`: The root of the repository.
+///
+/// # Returns
+///
+/// The date as a Unix timestamp and in strict ISO 8601 format.
+pub async fn git_last_commit(deps: &Dependencies, dir: &Path) -> Result<(i64, String)> {
+    let stdout = git_output(deps.git()?, dir, &["log", "-1", "--format=%ct %cI", "HEAD"]).await?;
+
+    let stdout = String::from_utf8_lossy(&stdout);
+    let (timestamp, date) = stdout.trim().split_once(' ').unwrap_or_default();
+    let timestamp = timestamp
+        .parse()
+        .map_err(|e| wrap_command_error(&format!("Invalid commit date '{}'", stdout.trim()), e))?;
+
+    Ok((timestamp, date.to_string()))
+}
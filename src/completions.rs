@@ -0,0 +1,186 @@
+use crate::config::Config;
+use clap::{Command, CommandFactory, ValueEnum};
+use clap_complete::{Shell, generate};
+use clap_complete_nushell::Nushell;
+use clap_mangen::Man;
+use std::io::{self, Write};
+
+/// Name of the binary the completions are registered for.
+const BIN_NAME: &str = "project-finder";
+
+/// A shell completion scripts can be generated for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum CompletionShell {
+    Bash,
+    Elvish,
+    Fish,
+    Nushell,
+    #[clap(name = "powershell")]
+    PowerShell,
+    Zsh,
+}
+
+/// Completes the query of `find` with the names of the projects below the current
+/// directory, and everything else with the generated function. It is registered with
+/// the same options as the generated function.
+const BASH_DYNAMIC: &str = r#"
+_project_finder_dynamic() {
+    local cur="${COMP_WORDS[COMP_CWORD]}"
+    if [[ "${COMP_WORDS[1]}" == find && "$COMP_CWORD" -eq 2 && "$cur" != -* ]]; then
+        local IFS=$'\n'
+        # shellcheck disable=SC2207
+        COMPREPLY=($(compgen -W "$(command project-finder --cached --format '{name}' 2>/dev/null)" -- "$cur"))
+        return
+    fi
+    _project__finder "$@"
+}
+
+if [[ "${BASH_VERSINFO[0]}" -eq 4 && "${BASH_VERSINFO[1]}" -ge 4 || "${BASH_VERSINFO[0]}" -gt 4 ]]; then
+    complete -F _project_finder_dynamic -o nosort -o bashdefault -o default project-finder
+else
+    complete -F _project_finder_dynamic -o bashdefault -o default project-finder
+fi
+"#;
+
+/// See [`BASH_DYNAMIC`]. It is registered in place of the generated function.
+const ZSH_DYNAMIC: &str = r#"
+_project_finder_dynamic() {
+    if [[ "${words[2]}" == find && "$CURRENT" -eq 3 && "${words[CURRENT]}" != -* ]]; then
+        local -a names
+        names=("${(@f)$(command project-finder --cached --format '{name}' 2>/dev/null)}")
+        compadd -a names
+        return
+    fi
+    _project-finder "$@"
+}
+"#;
+
+/// See [`BASH_DYNAMIC`].
+const FISH_DYNAMIC: &str = r#"
+complete -c project-finder -n "__fish_seen_subcommand_from find; and test (count (commandline -opc)) -eq 2" -f -a '(command project-finder --cached --format "{name}" 2>/dev/null)'
+"#;
+
+/// Write a completion script for a shell to stdout.
+///
+/// The script is generated from the command line definition. Bash, zsh and fish also
+/// complete the query of `find` with the names of the projects below the current
+/// directory.
+///
+/// # Errors
+///
+/// Returns an error if writing to stdout fails.
+pub fn print_completions(shell: CompletionShell) -> io::Result<()> {
+    let script = completion_script(shell);
+    let mut stdout = io::stdout().lock();
+    stdout.write_all(script.as_bytes())?;
+    stdout.flush()
+}
+
+/// Generate the completion script for a shell, see [`print_completions`].
+fn completion_script(shell: CompletionShell) -> String {
+    let mut command = Config::command();
+    let mut script = Vec::new();
+    match shell {
+        CompletionShell::Bash => generate(Shell::Bash, &mut command, BIN_NAME, &mut script),
+        CompletionShell::Elvish => generate(Shell::Elvish, &mut command, BIN_NAME, &mut script),
+        CompletionShell::Fish => generate(Shell::Fish, &mut command, BIN_NAME, &mut script),
+        CompletionShell::Nushell => generate(Nushell, &mut command, BIN_NAME, &mut script),
+        CompletionShell::PowerShell => {
+            generate(Shell::PowerShell, &mut command, BIN_NAME, &mut script);
+        }
+        CompletionShell::Zsh => generate(Shell::Zsh, &mut command, BIN_NAME, &mut script),
+    }
+
+    let mut script = String::from_utf8_lossy(&script).into_owned();
+    match shell {
+        CompletionShell::Bash => script.push_str(BASH_DYNAMIC),
+        CompletionShell::Fish => script.push_str(FISH_DYNAMIC),
+        CompletionShell::Zsh => script = with_zsh_dynamic(&script),
+        _ => {}
+    }
+
+    script
+}
+
+/// Insert the dynamic zsh function before the lines registering the generated one, and
+/// register it instead.
+///
+/// Those lines also call the function right away when the script is autoloaded from
+/// `fpath`, so both uses are redirected.
+fn with_zsh_dynamic(script: &str) -> String {
+    let Some(tail) = script.rfind("\nif [ \"$funcstack[1]\"") else {
+        return script.to_string();
+    };
+    let (functions, registration) = script.split_at(tail);
+    let registration = registration
+        .replace(
+            "    _project-finder \"$@\"",
+            "    _project_finder_dynamic \"$@\"",
+        )
+        .replace(
+            "compdef _project-finder project-finder",
+            "compdef _project_finder_dynamic project-finder",
+        );
+
+    format!("{functions}{ZSH_DYNAMIC}{registration}")
+}
+
+/// Write the man page, generated from the command line definition, to stdout.
+///
+/// The page of every subcommand follows the main one, as its `SUBCOMMANDS` section
+/// refers to them.
+///
+/// # Errors
+///
+/// Returns an error if writing to stdout fails.
+pub fn print_man() -> io::Result<()> {
+    let mut command = Config::command();
+    // Building names the subcommands `project-finder-<name>`, like the references.
+    command.build();
+
+    let source = format!("{BIN_NAME} {}", command.get_version().unwrap_or_default());
+
+    let mut stdout = io::stdout().lock();
+    render_man(&command, &source, &mut stdout)?;
+    stdout.flush()
+}
+
+/// Render the man page of a command followed by those of its subcommands.
+fn render_man(command: &Command, source: &str, out: &mut impl Write) -> io::Result<()> {
+    Man::new(command.clone())
+        .source(source.to_string())
+        .render(out)?;
+    for subcommand in command
+        .get_subcommands()
+        .filter(|subcommand| subcommand.get_name() != "help")
+    {
+        render_man(subcommand, source, out)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn zsh_registers_dynamic_function() {
+        let script = completion_script(CompletionShell::Zsh);
+        assert!(script.contains("\n_project_finder_dynamic() {"));
+        assert!(script.contains("compdef _project_finder_dynamic project-finder"));
+        assert!(script.contains("    _project_finder_dynamic \"$@\""));
+        assert!(!script.contains("compdef _project-finder project-finder"));
+    }
+
+    #[test]
+    fn bash_registers_dynamic_function_without_sorting() {
+        let script = completion_script(CompletionShell::Bash);
+        let (_, registration) = script
+            .split_once("\n_project_finder_dynamic() {")
+            .unwrap_or_default();
+        assert!(registration.contains(
+            "complete -F _project_finder_dynamic -o nosort -o bashdefault -o default project-finder"
+        ));
+    }
+}
@@ -1,26 +1,326 @@
-use clap::Parser;
-use std::path::PathBuf;
+use crate::{
+    completions::CompletionShell, init::Shell, output::OutputFormat, project::ProjectKind,
+    sort::SortOrder, template::STATUS_FIELDS,
+};
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use std::{
+    env,
+    io::{self, IsTerminal},
+    path::PathBuf,
+};
 
+#[allow(clippy::struct_excessive_bools)]
 #[derive(Debug, Parser, Clone)]
 #[clap(
     author,
     version,
-    about = "Find coding projects in specified directories"
+    about = "Find coding projects in specified directories",
+    args_conflicts_with_subcommands = true
 )]
 pub struct Config {
+    #[clap(subcommand)]
+    pub command: Option<Command>,
+
     /// Directories to search for projects
     #[clap(default_value = ".")]
     pub paths: Vec<PathBuf>,
 
     /// Maximum search depth
-    #[clap(short, long, default_value = "5")]
+    #[clap(short, long, default_value = "5", global = true)]
     pub depth: usize,
 
     /// Show verbose output
-    #[clap(short, long)]
+    #[clap(short, long, global = true)]
     pub verbose: bool,
 
     /// Maximum number of results to return
-    #[clap(short = 'n', long, default_value = "0")]
+    #[clap(short = 'n', long, default_value = "0", global = true)]
     pub max_results: usize,
+
+    /// Order of the results, applied before `--max-results`
+    #[clap(long, value_name = "ORDER", default_value = "path", global = true)]
+    pub sort: SortOrder,
+
+    /// Reverse the order of the results
+    #[clap(long, global = true)]
+    pub reverse: bool,
+
+    /// Output format: `text`, `json`, `ndjson` or a template such as
+    /// '{name}\t{path:tilde}'
+    #[clap(short, long, default_value = "text", global = true)]
+    pub format: OutputFormat,
+
+    /// Separate results with NUL instead of newline, for use with `xargs -0`
+    #[clap(short = '0', long, global = true)]
+    pub print0: bool,
+
+    /// Pick a project interactively and print its path
+    #[clap(long, conflicts_with_all = ["format", "print0", "tree"])]
+    pub pick: bool,
+
+    /// Show projects as a tree with their workspace members and nested projects
+    #[clap(long, global = true, conflicts_with_all = ["format", "print0"])]
+    pub tree: bool,
+
+    /// List linked Git worktrees under their main repository instead of on their own
+    #[clap(long, global = true)]
+    pub group_worktrees: bool,
+
+    /// How Git submodules are reported
+    #[clap(long, value_name = "MODE", default_value = "show", global = true)]
+    pub submodules: SubmoduleMode,
+
+    /// Also report bare Git repositories, such as mirrors
+    #[clap(long, global = true)]
+    pub include_bare: bool,
+
+    /// Take the projects from the project index instead of scanning, building the index
+    /// first if it does not cover the search paths
+    #[clap(long, global = true)]
+    pub cached: bool,
+
+    /// Only show projects of the given kinds
+    #[clap(
+        short = 't',
+        long = "type",
+        value_name = "KIND",
+        value_delimiter = ',',
+        global = true
+    )]
+    pub types: Vec<ProjectKind>,
+
+    /// Exclude projects of the given kinds
+    #[clap(
+        long = "not-type",
+        value_name = "KIND",
+        value_delimiter = ',',
+        global = true
+    )]
+    pub exclude_types: Vec<ProjectKind>,
+
+    /// Only show projects whose Git branch matches the glob pattern
+    #[clap(long, value_name = "GLOB", global = true)]
+    pub branch: Option<String>,
+
+    /// Only show projects with a Git remote whose normalized URL matches the glob pattern,
+    /// such as 'github.com/acme/*'
+    #[clap(long, value_name = "GLOB", global = true)]
+    pub remote: Option<String>,
+
+    /// Only show projects whose Git `HEAD` is detached
+    #[clap(long, global = true)]
+    pub detached: bool,
+
+    /// Only show projects with a merge, rebase, cherry-pick or similar in progress
+    #[clap(long, global = true)]
+    pub in_progress: bool,
+
+    /// Report the working tree status of each Git repository
+    #[clap(long, global = true)]
+    pub git_status: bool,
+
+    /// Only show projects with uncommitted changes or untracked files
+    #[clap(long, conflicts_with = "clean", global = true)]
+    pub dirty: bool,
+
+    /// Only show projects whose working tree is clean
+    #[clap(long, global = true)]
+    pub clean: bool,
+}
+
+/// How Git submodules are reported.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum SubmoduleMode {
+    /// Report submodules as projects of their own
+    #[default]
+    Show,
+    /// Only list submodules under their superproject
+    Nest,
+    /// Leave submodules out entirely
+    Hide,
+}
+
+#[derive(Debug, Subcommand, Clone)]
+pub enum Command {
+    /// Print the projects affected by a list of changed files
+    Affected(AffectedArgs),
+    /// List repositories with local branches that are not pushed to any remote, or
+    /// without a remote at all
+    Unpushed(UnpushedArgs),
+    /// Group Git repositories that are clones of the same repository, by remote and
+    /// root commit
+    Duplicates(DuplicatesArgs),
+    /// Rank projects by how well their name, path and description fuzzy match a query
+    Find(FindArgs),
+    /// Record a visit of a directory in the frecency database, for use in shell hooks
+    Visit(VisitArgs),
+    /// Print the most frecent visited directory matching all keywords
+    Jump(JumpArgs),
+    /// Print shell functions for changing into projects, recording visits and
+    /// completing project names
+    Init(InitArgs),
+    /// Print a completion script for a shell
+    Completions(CompletionsArgs),
+    /// Print the man page
+    Man,
+    /// Manage the project index used by `--cached`
+    Index(IndexArgs),
+}
+
+#[derive(Debug, Args, Clone)]
+pub struct AffectedArgs {
+    /// Changed files (read from stdin, one per line, when omitted)
+    pub files: Vec<PathBuf>,
+
+    /// Take the changed files from `git diff --name-only <REV>` instead
+    #[clap(long, value_name = "REV", conflicts_with = "files")]
+    pub since: Option<String>,
+
+    /// Also include projects depending on the affected ones through local path
+    /// dependencies
+    #[clap(long)]
+    pub dependents: bool,
+
+    /// Directories scanned for dependent projects
+    #[clap(long = "search", value_name = "PATH", default_value = ".")]
+    pub search_paths: Vec<PathBuf>,
+
+    /// Resolve files to the nearest package instead of its workspace root
+    #[clap(long)]
+    pub members: bool,
+}
+
+#[derive(Debug, Args, Clone)]
+pub struct UnpushedArgs {
+    /// Directories to search for repositories
+    #[clap(default_value = ".")]
+    pub paths: Vec<PathBuf>,
+}
+
+#[derive(Debug, Args, Clone)]
+pub struct DuplicatesArgs {
+    /// Directories to search for repositories
+    #[clap(default_value = ".")]
+    pub paths: Vec<PathBuf>,
+}
+
+#[derive(Debug, Args, Clone)]
+pub struct FindArgs {
+    /// Whitespace separated terms, each of which has to match
+    pub query: String,
+
+    /// Directories to search for projects
+    #[clap(default_value = ".")]
+    pub paths: Vec<PathBuf>,
+
+    /// When to highlight the matched characters
+    #[clap(long, value_name = "WHEN", default_value = "auto")]
+    pub color: ColorMode,
+}
+
+/// When to use colors in the output.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum ColorMode {
+    /// Only when writing to a terminal and `NO_COLOR` is not set
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+impl ColorMode {
+    /// Whether colors are used when writing to stdout.
+    pub fn enabled(self) -> bool {
+        match self {
+            Self::Auto => io::stdout().is_terminal() && env::var_os("NO_COLOR").is_none(),
+            Self::Always => true,
+            Self::Never => false,
+        }
+    }
+}
+
+#[derive(Debug, Args, Clone)]
+pub struct VisitArgs {
+    /// Directory that was visited
+    #[clap(default_value = ".")]
+    pub path: PathBuf,
+}
+
+#[derive(Debug, Args, Clone)]
+pub struct JumpArgs {
+    /// Keywords that have to appear in order in the path, the last one in its final
+    /// component
+    pub keywords: Vec<String>,
+}
+
+#[derive(Debug, Args, Clone)]
+pub struct InitArgs {
+    /// Shell to generate the functions for
+    pub shell: Shell,
+
+    /// Directories the generated function searches for projects (default: home
+    /// directory)
+    pub paths: Vec<PathBuf>,
+
+    /// Name of the function changing into a project
+    #[clap(long, value_name = "NAME", default_value = "pcd")]
+    pub cmd: String,
+}
+
+#[derive(Debug, Args, Clone)]
+pub struct CompletionsArgs {
+    /// Shell to generate the completion script for
+    pub shell: CompletionShell,
+}
+
+#[derive(Debug, Args, Clone)]
+pub struct IndexArgs {
+    #[clap(subcommand)]
+    pub action: IndexAction,
+}
+
+#[derive(Debug, Subcommand, Clone)]
+pub enum IndexAction {
+    /// Scan directories and add them to the index, along with the directories already in
+    /// it, of which only those that changed are rescanned. Paths are only removed from
+    /// the index by `clear`
+    Rebuild(RebuildArgs),
+    /// Rescan the directories of the index that changed since it was built, keeping its
+    /// paths and depth, if there are any
+    Refresh(RefreshArgs),
+    /// Show what the index contains and how many directories changed since it was
+    /// built
+    Status,
+    /// Remove the index
+    Clear,
+}
+
+#[derive(Debug, Args, Clone)]
+pub struct RebuildArgs {
+    /// Directories to add to the index (default: the current directory if there is no
+    /// index yet)
+    pub paths: Vec<PathBuf>,
+
+    /// Scan everything again instead of only the directories that changed since the
+    /// index was built
+    #[clap(long)]
+    pub full: bool,
+}
+
+#[derive(Debug, Args, Clone)]
+pub struct RefreshArgs {
+    /// Refresh in a background process and return immediately, as the shell hooks do
+    #[clap(long)]
+    pub detach: bool,
+}
+
+impl Config {
+    /// Whether the working tree status of repositories has to be read.
+    pub fn needs_git_status(&self) -> bool {
+        let template_uses_status = matches!(
+            &self.format,
+            OutputFormat::Template(template) if template.uses_any(&STATUS_FIELDS)
+        );
+        self.git_status || self.dirty || self.clean || template_uses_status
+    }
 }
@@ -3,11 +3,14 @@ use tracing::info;
 use which::which;
 
 const FD_PATH: [&str; 2] = ["fd", "fdfind"];
+const GIT_PATH: &str = "git";
 
 /// Represents external dependencies required by the application.
 #[derive(Debug, Clone)]
 pub struct Dependencies {
     pub fd_path: String,
+    /// Path to `git`, which is only needed by features inspecting working trees.
+    pub git_path: Option<String>,
 }
 
 impl Dependencies {
@@ -15,13 +18,28 @@ impl Dependencies {
     pub fn new(fd_path: impl Into<String>) -> Self {
         Self {
             fd_path: fd_path.into(),
+            git_path: None,
         }
     }
 
+    /// Returns the path to `git`.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `ProjectFinderError::DependencyNotFound` error if `git` was not found.
+    pub fn git(&self) -> Result<&str> {
+        self.git_path.as_deref().ok_or_else(|| {
+            ProjectFinderError::DependencyNotFound(
+                "'git' was not found. Please install git from https://git-scm.com".into(),
+            )
+        })
+    }
+
     /// Checks if all required dependencies are available, returning an instance of
     /// `Dependencies` with the paths set appropriately.
     ///
-    /// At the moment, this only verifies that the `fd` binary is available.
+    /// The `fd` binary is required. `git` is optional and only looked up, since most
+    /// features read repositories without it.
     ///
     /// # Errors
     ///
@@ -46,6 +64,15 @@ impl Dependencies {
                 )
             })?;
 
-        Ok(Self::new(fd_path))
+        let git_path = which(GIT_PATH).ok().map(|path| {
+            let git_path = path.to_string_lossy().into_owned();
+            info!("Found {GIT_PATH} at: {git_path}");
+            git_path
+        });
+
+        Ok(Self {
+            git_path,
+            ..Self::new(fd_path)
+        })
     }
 }
@@ -0,0 +1,42 @@
+use crate::errors::{ProjectFinderError, Result};
+use std::{
+    env::{self, home_dir},
+    path::PathBuf,
+};
+
+/// Directory holding persistent data such as the visit database:
+/// `$PROJECT_FINDER_DATA_DIR`, or `project-finder` in `$XDG_DATA_HOME` or
+/// `~/.local/share`.
+///
+/// # Errors
+///
+/// Returns an error if neither variable nor the home directory is available.
+pub fn data_dir() -> Result<PathBuf> {
+    app_dir("PROJECT_FINDER_DATA_DIR", "XDG_DATA_HOME", ".local/share")
+        .ok_or(ProjectFinderError::DataDirNotFound)
+}
+
+/// Directory holding data that can be rebuilt at any time, such as the project index:
+/// `$PROJECT_FINDER_CACHE_DIR`, or `project-finder` in `$XDG_CACHE_HOME` or `~/.cache`.
+///
+/// # Errors
+///
+/// Returns an error if neither variable nor the home directory is available.
+pub fn cache_dir() -> Result<PathBuf> {
+    app_dir("PROJECT_FINDER_CACHE_DIR", "XDG_CACHE_HOME", ".cache")
+        .ok_or(ProjectFinderError::CacheDirNotFound)
+}
+
+/// Resolve a directory of project-finder from its own override variable, an XDG base
+/// directory variable, or its default location below the home directory.
+fn app_dir(override_var: &str, xdg_var: &str, default: &str) -> Option<PathBuf> {
+    if let Some(dir) = env::var_os(override_var) {
+        return Some(dir.into());
+    }
+
+    env::var_os(xdg_var)
+        .map(PathBuf::from)
+        .filter(|dir| dir.is_absolute())
+        .or_else(|| home_dir().map(|home| home.join(default)))
+        .map(|dir| dir.join("project-finder"))
+}
@@ -0,0 +1,125 @@
+use crate::{errors::Result, finder::ProjectFinder, project::Project};
+use std::collections::HashMap;
+
+/// Clones of the same repository found in different places.
+#[derive(Debug, Clone)]
+pub struct DuplicateGroup {
+    /// Normalized remote URL shared by the clones, if any.
+    pub remote: Option<String>,
+    /// Root commit shared by the clones, if any.
+    pub root_commit: Option<String>,
+    pub projects: Vec<Project>,
+}
+
+/// Find Git repositories that are clones of the same repository.
+///
+/// Two repositories are considered clones if they share a remote, compared by its
+/// normalized URL, or a root commit. Clones of clones end up in the same group. Only
+/// the repositories passing the filters given on the command line are compared. The
+/// latest commit and working tree status of every clone are read so they can be told
+/// apart.
+///
+/// # Returns
+///
+/// The groups with more than one repository, sorted by their first root.
+pub async fn find_duplicates(finder: &ProjectFinder) -> Result<Vec<DuplicateGroup>> {
+    let mut projects = finder.find_repositories().await?;
+    finder.read_git_statuses(&mut projects).await?;
+    projects.retain(|project| finder.matches(project));
+    finder.read_root_commits(&mut projects).await?;
+    finder.read_last_commits(&mut projects).await?;
+
+    // Union the repositories sharing any key, a remote or a root commit.
+    let mut parents = (0..projects.len()).collect::<Vec<_>>();
+    let mut owners = HashMap::<String, usize>::new();
+    for (index, project) in projects.iter().enumerate() {
+        for key in keys(project) {
+            match owners.get(&key) {
+                Some(&owner) => union(&mut parents, owner, index),
+                None => {
+                    owners.insert(key, index);
+                }
+            }
+        }
+    }
+
+    let mut groups = HashMap::<usize, Vec<Project>>::new();
+    for (index, project) in projects.into_iter().enumerate() {
+        let group = find(&mut parents, index);
+        groups.entry(group).or_default().push(project);
+    }
+
+    let mut groups = groups
+        .into_values()
+        .filter(|projects| projects.len() > 1)
+        .map(|projects| DuplicateGroup {
+            remote: shared(&projects, |project| remotes(project).collect()),
+            root_commit: shared(&projects, |project| project.root_commits.clone()),
+            projects,
+        })
+        .collect::<Vec<_>>();
+    groups.sort_by(|a, b| a.projects[0].root.cmp(&b.projects[0].root));
+
+    Ok(groups)
+}
+
+fn remotes(project: &Project) -> impl Iterator<Item = String> + '_ {
+    project
+        .remotes
+        .iter()
+        .filter_map(|remote| remote.normalized.clone())
+}
+
+/// Keys identifying the repository a project is a clone of.
+fn keys(project: &Project) -> Vec<String> {
+    let root_commits = project
+        .root_commits
+        .iter()
+        .map(|hash| format!("commit:{hash}"));
+
+    remotes(project)
+        .map(|url| format!("remote:{url}"))
+        .chain(root_commits)
+        .collect()
+}
+
+/// Find the value held by the most projects of a group, if at least two share it.
+fn shared(projects: &[Project], values: impl Fn(&Project) -> Vec<String>) -> Option<String> {
+    let mut counts = HashMap::<String, usize>::new();
+    for project in projects {
+        let mut values = values(project);
+        values.sort();
+        values.dedup();
+        for value in values {
+            *counts.entry(value).or_default() += 1;
+        }
+    }
+
+    counts
+        .into_iter()
+        .filter(|(_, count)| *count > 1)
+        .max_by(|(a, a_count), (b, b_count)| a_count.cmp(b_count).then_with(|| b.cmp(a)))
+        .map(|(value, _)| value)
+}
+
+fn find(parents: &mut [usize], index: usize) -> usize {
+    let mut root = index;
+    while parents[root] != root {
+        root = parents[root];
+    }
+    // Compress the path so later lookups are direct.
+    let mut current = index;
+    while parents[current] != root {
+        let next = parents[current];
+        parents[current] = root;
+        current = next;
+    }
+    root
+}
+
+fn union(parents: &mut [usize], a: usize, b: usize) {
+    let (a, b) = (find(parents, a), find(parents, b));
+    if a != b {
+        parents[b] = a;
+    }
+}
@@ -1,4 +1,4 @@
-use std::{path::PathBuf, string::FromUtf8Error};
+use std::path::PathBuf;
 use thiserror::Error;
 
 #[derive(Debug, Error)]
@@ -15,8 +15,20 @@ pub enum ProjectFinderError {
     #[error("IO error: {0}")]
     IoError(#[from] std::io::Error),
 
-    #[error("Invalid UTF-8: {0}")]
-    Utf8Error(#[from] FromUtf8Error),
+    #[error("Failed to parse manifest {0}: {1}")]
+    ManifestParseFailed(PathBuf, String),
+
+    #[error("Failed to parse visit database {0}: {1}")]
+    DatabaseParseFailed(PathBuf, String),
+
+    #[error("Could not determine the data directory, set XDG_DATA_HOME or HOME")]
+    DataDirNotFound,
+
+    #[error("Failed to parse project index {0}: {1}")]
+    IndexParseFailed(PathBuf, String),
+
+    #[error("Could not determine the cache directory, set XDG_CACHE_HOME or HOME")]
+    CacheDirNotFound,
 }
 
 pub type Result<T> = std::result::Result<T, ProjectFinderError>;
@@ -0,0 +1,85 @@
+use crate::{config::Config, project::Project};
+
+/// Match `text` against a glob pattern where `*` matches any run of characters and `?`
+/// matches a single character.
+pub fn glob_matches(pattern: &str, text: &str) -> bool {
+    let pattern = pattern.chars().collect::<Vec<_>>();
+    let text = text.chars().collect::<Vec<_>>();
+
+    let (mut p, mut t) = (0, 0);
+    let mut backtrack = None;
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, t));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match backtrack {
+                Some((star, matched)) => {
+                    p = star + 1;
+                    t = matched + 1;
+                    backtrack = Some((star, matched + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// Check whether a project passes all filters given on the command line.
+pub fn matches(project: &Project, config: &Config) -> bool {
+    if !project.matches_kinds(&config.types, &config.exclude_types) {
+        return false;
+    }
+
+    if project.bare && !config.include_bare {
+        return false;
+    }
+
+    let git = project.git.as_ref();
+
+    if let Some(pattern) = &config.branch {
+        let branch = git.and_then(|git| git.branch.as_deref());
+        if !branch.is_some_and(|branch| glob_matches(pattern, branch)) {
+            return false;
+        }
+    }
+
+    if let Some(pattern) = &config.remote {
+        let matched = project.remotes.iter().any(|remote| {
+            remote
+                .normalized
+                .as_deref()
+                .is_some_and(|url| glob_matches(pattern, url))
+        });
+        if !matched {
+            return false;
+        }
+    }
+
+    if config.detached && !git.is_some_and(|git| git.detached) {
+        return false;
+    }
+
+    if config.in_progress && git.is_none_or(|git| git.operation.is_none()) {
+        return false;
+    }
+
+    let status = project.status.as_ref();
+
+    if config.dirty && status.is_none_or(|status| !status.dirty) {
+        return false;
+    }
+
+    if config.clean && status.is_none_or(|status| status.dirty) {
+        return false;
+    }
+
+    true
+}
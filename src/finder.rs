@@ -1,27 +1,54 @@
 use crate::{
-    commands::{find_files, find_git_repos, grep_file_in_memory},
-    config::Config,
+    commands::{
+        find_bare_repos, find_dirs, find_files, find_repos, git_last_commit, git_root_commits,
+        git_status, git_unpushed_branches, grep_file_in_memory,
+    },
+    config::{Config, SubmoduleMode},
     dependencies::Dependencies,
     errors::{ProjectFinderError, Result},
+    filter,
+    frecency::{self, Database},
+    git::{
+        LastCommit, Unpushed, UnpushedBranch, is_bare, is_repo_root, linked_worktrees,
+        main_worktree, parse_status, read_git_info, read_remotes, read_submodules, stash_count,
+        superproject,
+    },
+    index::{Caches, Index, Listing},
+    manifest::read_metadata,
     marker::MarkerType,
+    project::Project,
+    sort::{SortOrder, sort_projects},
+    vcs::{self, is_vcs_root},
+};
+use futures::{
+    StreamExt,
+    future::join_all,
+    stream::{self},
 };
-use futures::future::join_all;
 use std::{
-    collections::{HashMap, HashSet},
-    path::{Path, PathBuf},
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+    path::{Path, PathBuf, absolute},
     sync::Arc,
+    time::SystemTime,
 };
 use tokio::{
     fs::metadata,
     spawn,
     sync::{RwLock, Semaphore},
 };
-use tracing::{debug, info};
+use tracing::{debug, info, warn};
 
-type ProjectSet = Arc<RwLock<HashSet<PathBuf>>>;
+type ProjectMap = Arc<RwLock<HashMap<PathBuf, Project>>>;
 type WorkspaceCache = Arc<RwLock<HashMap<PathBuf, bool>>>;
 type RootCache = Arc<RwLock<HashMap<(PathBuf, String), PathBuf>>>;
 
+/// Maximum number of repositories inspected with `git` at the same time.
+const GIT_CONCURRENCY: usize = 8;
+
+/// Maximum number of directories checked for changes at the same time, high enough to
+/// hide the latency of network file systems.
+const STAT_CONCURRENCY: usize = 64;
+
 const MARKER_PATTERNS: [&str; 13] = [
     "package.json",
     "pnpm-workspace.yaml",
@@ -38,17 +65,92 @@ const MARKER_PATTERNS: [&str; 13] = [
     "bunfig.toml",
 ];
 
+/// Modification times of directories, `None` for those that are gone.
+async fn modification_times(dirs: Vec<PathBuf>) -> Vec<(PathBuf, Option<SystemTime>)> {
+    stream::iter(dirs)
+        .map(|dir| async move {
+            let modified = metadata(&dir).await.and_then(|m| m.modified()).ok();
+            (dir, modified)
+        })
+        .buffer_unordered(STAT_CONCURRENCY)
+        .collect()
+        .await
+}
+
+/// Directories of `dirs` whose modification time changed, with the new one, and those
+/// that are gone.
+async fn changed_dirs(
+    dirs: &BTreeMap<PathBuf, SystemTime>,
+) -> (Vec<(PathBuf, SystemTime)>, Vec<PathBuf>) {
+    let mut changed = Vec::new();
+    let mut removed = Vec::new();
+    for (dir, modified) in modification_times(dirs.keys().cloned().collect()).await {
+        match modified {
+            Some(modified) if dirs.get(&dir) == Some(&modified) => {}
+            Some(modified) => changed.push((dir, modified)),
+            None => removed.push(dir),
+        }
+    }
+    (changed, removed)
+}
+
 /// Check whether a given path exists.
 async fn path_exists(path: &Path) -> bool {
     metadata(path).await.is_ok()
 }
 
+/// Find the closest of a directory and its ancestors that `is_root` accepts.
+async fn closest_ancestor(dir: &Path, is_root: impl AsyncFn(&Path) -> bool) -> Option<PathBuf> {
+    for ancestor in dir.ancestors() {
+        if ancestor.as_os_str().is_empty() {
+            break;
+        }
+        if is_root(ancestor).await {
+            return Some(ancestor.to_path_buf());
+        }
+    }
+    None
+}
+
+/// Drop the projects inside linked worktrees whose main working tree is listed as well.
+///
+/// The main working tree keeps listing them in its `worktrees`.
+pub fn group_worktrees(projects: &mut Vec<Project>) {
+    let roots = projects
+        .iter()
+        .map(|project| project.root.clone())
+        .collect::<HashSet<_>>();
+    projects.retain(|project| {
+        project
+            .main_worktree
+            .as_ref()
+            .is_none_or(|main| !roots.contains(main))
+    });
+}
+
+/// Drop the projects inside submodules unless they are to be shown on their own.
+///
+/// With [`SubmoduleMode::Nest`] the superprojects keep listing them in their
+/// `submodules`, with [`SubmoduleMode::Hide`] they are not reported at all.
+pub fn apply_submodule_mode(projects: &mut Vec<Project>, mode: SubmoduleMode) {
+    if mode == SubmoduleMode::Show {
+        return;
+    }
+
+    projects.retain(|project| project.superproject.is_none());
+    if mode == SubmoduleMode::Hide {
+        for project in projects {
+            project.submodules.clear();
+        }
+    }
+}
+
 /// Struct responsible for scanning directories and detecting projects.
 #[derive(Debug, Clone)]
 pub struct ProjectFinder {
     config: Config,
     deps: Dependencies,
-    discovered_projects: ProjectSet,
+    discovered_projects: ProjectMap,
     workspace_cache: WorkspaceCache,
     root_cache: RootCache,
 }
@@ -59,14 +161,103 @@ impl ProjectFinder {
         Self {
             config,
             deps,
-            discovered_projects: Arc::new(RwLock::new(HashSet::new())),
+            discovered_projects: Arc::new(RwLock::new(HashMap::new())),
             workspace_cache: Arc::new(RwLock::new(HashMap::new())),
             root_cache: Arc::new(RwLock::new(HashMap::new())),
         }
     }
 
     /// Find projects in the configured paths.
-    pub async fn find_projects(&self) -> Result<Vec<PathBuf>> {
+    ///
+    /// Discovered projects are filtered, sorted in the configured order and then limited
+    /// to `max_results`.
+    pub async fn find_projects(&self) -> Result<Vec<Project>> {
+        let mut projects = self.select_projects().await?;
+        self.sort_projects(&mut projects).await?;
+        if self.config.max_results > 0 && projects.len() > self.config.max_results {
+            projects.truncate(self.config.max_results);
+        }
+
+        Ok(projects)
+    }
+
+    /// Apply the filters, order and limit of [`Self::find_projects`] to projects found
+    /// by a subcommand, reading their working tree status first if it is needed.
+    pub async fn select_found(&self, mut projects: Vec<Project>) -> Result<Vec<Project>> {
+        if self.config.needs_git_status() {
+            self.read_git_statuses(&mut projects).await?;
+        }
+        projects.retain(|project| self.matches(project));
+        self.sort_projects(&mut projects).await?;
+        if self.config.max_results > 0 {
+            projects.truncate(self.config.max_results);
+        }
+
+        Ok(projects)
+    }
+
+    /// Check whether a project passes the filters given on the command line.
+    pub fn matches(&self, project: &Project) -> bool {
+        filter::matches(project, &self.config)
+    }
+
+    /// Discover the projects in the configured paths that pass the filters, with
+    /// worktrees and submodules reported as configured, in no particular order.
+    pub async fn select_projects(&self) -> Result<Vec<Project>> {
+        // The details are only read for projects of the requested kinds.
+        let mut projects = self
+            .discover_where(|project| {
+                project.matches_kinds(&self.config.types, &self.config.exclude_types)
+            })
+            .await?;
+        if self.config.needs_git_status() {
+            self.read_git_statuses(&mut projects).await?;
+        }
+
+        projects.retain(|project| self.matches(project));
+        if self.config.group_worktrees {
+            group_worktrees(&mut projects);
+        }
+        apply_submodule_mode(&mut projects, self.config.submodules);
+
+        Ok(projects)
+    }
+
+    /// Sort projects in the configured order, reading the commits or visits it is
+    /// based on first.
+    pub async fn sort_projects(&self, projects: &mut [Project]) -> Result<()> {
+        if self.config.sort == SortOrder::LastCommit {
+            self.read_last_commits(projects).await?;
+        }
+        if self.config.sort == SortOrder::Frecency {
+            Database::open()?.score_projects(projects, frecency::now());
+        }
+        sort_projects(projects, self.config.sort, self.config.reverse);
+
+        Ok(())
+    }
+
+    /// Discover all projects in the configured paths and fill in their details, without
+    /// filtering or ordering them.
+    ///
+    /// With `--cached` they are taken from the project index instead of scanning.
+    pub async fn discover_projects(&self) -> Result<Vec<Project>> {
+        self.discover_where(|_| true).await
+    }
+
+    /// Discover the projects in the configured paths that pass `keep`, which sees them
+    /// before their details are filled in.
+    async fn discover_where(&self, keep: impl Fn(&Project) -> bool) -> Result<Vec<Project>> {
+        if self.config.cached {
+            self.discover_cached(keep).await
+        } else {
+            self.scan().await?;
+            self.enrich_discovered(keep).await
+        }
+    }
+
+    /// Scan the configured paths for projects, returning what was found.
+    async fn scan(&self) -> Result<Listing> {
         let semaphore = Arc::new(Semaphore::new(8)); // Limit to 8 concurrent tasks
         let mut handles = Vec::new();
 
@@ -89,111 +280,798 @@ impl ProjectFinder {
                         "Failed to aquire semaphore: {e}"
                     ))
                 })?;
-                finder_clone.process_directory(&path_clone).await
+                let listing = finder_clone
+                    .list_directory(&path_clone, finder_clone.config.depth)
+                    .await?;
+                finder_clone.process_listing(&listing).await?;
+                Ok::<_, ProjectFinderError>(listing)
             });
             handles.push(handle);
         }
 
         // Await all tasks and collect errors.
         let handle_results = join_all(handles).await;
-        let mut errors = handle_results
-            .into_iter()
-            .filter_map(|handle_result| match handle_result {
-                Ok(task_result) => task_result.err().map(|e| {
+        let mut listing = Listing::default();
+        let mut errors = Vec::new();
+        for handle_result in handle_results {
+            match handle_result {
+                Ok(Ok(found)) => listing.extend(found),
+                Ok(Err(e)) => {
                     debug!("Task failed: {e}");
-                    e
-                }),
+                    errors.push(e);
+                }
                 Err(e) => {
                     debug!("Task join error: {e}");
-                    Some(ProjectFinderError::CommandExecutionFailed(format!(
+                    errors.push(ProjectFinderError::CommandExecutionFailed(format!(
                         "Task panicked: {e}",
-                    )))
+                    )));
                 }
-            })
-            .collect::<Vec<_>>();
+            }
+        }
 
         // If all tasks failed, return one of the errors.
         if !errors.is_empty() && errors.len() == self.config.paths.len() {
             return Err(errors.remove(0));
         }
 
-        // Gather discovered projects, sort and apply max_results limit, if set.
-        let mut projects = self
+        Ok(listing)
+    }
+
+    /// Fill in the details of the discovered projects that pass `keep`.
+    ///
+    /// At most `GIT_CONCURRENCY` projects are enriched at the same time, as each one
+    /// runs several `git` commands.
+    async fn enrich_discovered(&self, keep: impl Fn(&Project) -> bool) -> Result<Vec<Project>> {
+        let discovered = self
             .discovered_projects
             .read()
             .await
+            .values()
+            .filter(|project| keep(project))
+            .cloned()
+            .collect::<Vec<_>>();
+
+        let semaphore = Semaphore::new(GIT_CONCURRENCY);
+        let enriched = join_all(discovered.into_iter().map(|mut project| {
+            let semaphore = &semaphore;
+            async move {
+                let _permit = semaphore.acquire().await.map_err(|e| {
+                    ProjectFinderError::CommandExecutionFailed(format!(
+                        "Failed to aquire semaphore: {e}"
+                    ))
+                })?;
+                self.enrich_project(&mut project).await.map(|()| project)
+            }
+        }))
+        .await;
+
+        enriched.into_iter().collect()
+    }
+
+    /// Take the projects in the configured paths that pass `keep` from the project
+    /// index, building it first if it does not cover them.
+    ///
+    /// Paths that are not indexed yet are added to the index along with the ones that
+    /// are, so searches of different paths do not replace each other's index. An index
+    /// that cannot be read is built again.
+    ///
+    /// Only the Git state is read again, as it is cheap and changes often. Everything
+    /// else is as it was when the index was built.
+    async fn discover_cached(&self, keep: impl Fn(&Project) -> bool) -> Result<Vec<Project>> {
+        let mut paths = Vec::with_capacity(self.config.paths.len());
+        for path in &self.config.paths {
+            if !path.is_dir() {
+                return Err(ProjectFinderError::PathNotFound(path.clone()));
+            }
+            paths.push(absolute(path)?);
+        }
+
+        let index = Index::load().unwrap_or_else(|e| {
+            warn!("Rebuilding the project index: {e}");
+            None
+        });
+        let index = match index {
+            Some(index) if index.covers(&paths, self.config.depth) => index,
+            Some(index) => {
+                let depth = index.depth.max(self.config.depth);
+                self.build_index(index.merged_paths(&paths), depth, Some(index))
+                    .await?
+            }
+            None => {
+                self.build_index(paths.clone(), self.config.depth, None)
+                    .await?
+            }
+        };
+
+        let mut projects = Vec::new();
+        for mut project in index.projects {
+            if (project.bare && !self.config.include_bare)
+                || !self.found_in(&mut project, &paths)
+                || !keep(&project)
+            {
+                continue;
+            }
+
+            if let Some(git_root) = &project.git_root {
+                project.git = read_git_info(git_root).await;
+            }
+            project.map_paths(|path| self.as_found(path));
+            project.depth = self.depth_below_paths(&project.root);
+            projects.push(project);
+        }
+
+        Ok(projects)
+    }
+
+    /// Check whether a scan of the absolute `paths` would find an indexed project, as
+    /// one of its markers or the repository at its root is within the search depth.
+    ///
+    /// The markers the scan would not reach are dropped, along with their kinds, and so
+    /// are those of members.
+    fn found_in(&self, project: &mut Project, paths: &[PathBuf]) -> bool {
+        let within_depth = |entry: &Path| {
+            paths.iter().any(|path| {
+                entry
+                    .strip_prefix(path)
+                    .is_ok_and(|rest| rest.components().count() <= self.config.depth)
+            })
+        };
+
+        project.kinds.clear();
+        let markers = std::mem::take(&mut project.markers);
+        let members = std::mem::take(&mut project.members);
+        for marker in markers.into_iter().chain(members) {
+            if within_depth(&marker) {
+                project.add_marker(marker);
+            }
+        }
+
+        // Repositories are found by the `.git` or `HEAD` entry inside their root.
+        let is_repo = project.bare || project.vcs_root.as_ref() == Some(&project.root);
+        (is_repo && within_depth(&project.root.join(".git")))
+            || !project.markers.is_empty()
+            || !project.members.is_empty()
+    }
+
+    /// Scan the absolute `paths` up to `depth` for the project index and save it,
+    /// including bare repositories so the index serves searches with and without
+    /// `--include-bare`.
+    ///
+    /// Given the previous index of the same depth and some of the same paths, only the
+    /// directories whose modification time changed since are listed again and only the
+    /// new paths are scanned in full, see [`Self::rescan`]. Given any previous index,
+    /// the workspace and project roots resolved for it are reused, except in directories
+    /// that changed since.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a path cannot be scanned or the index cannot be saved.
+    pub async fn build_index(
+        &self,
+        paths: Vec<PathBuf>,
+        depth: usize,
+        previous: Option<Index>,
+    ) -> Result<Index> {
+        let mut config = self.config.clone();
+        config.paths.clone_from(&paths);
+        config.depth = depth;
+        config.include_bare = true;
+        let finder = Self::new(config, self.deps.clone());
+
+        if previous.is_some() {
+            finder.load_caches().await;
+        }
+        let (reusable, outdated) = match previous {
+            Some(index)
+                if index.depth == depth && index.paths.iter().all(|path| paths.contains(path)) =>
+            {
+                (Some(index), None)
+            }
+            index => (None, index),
+        };
+
+        let (dirs, listing, projects) = if let Some(previous) = reusable {
+            finder.rescan(previous).await?
+        } else {
+            if let Some(outdated) = outdated {
+                let (changed, removed) = changed_dirs(&outdated.dirs).await;
+                let changed = changed.into_iter().map(|(dir, _)| dir).collect::<Vec<_>>();
+                finder.forget_roots(&changed, &removed).await;
+            }
+
+            // Times are taken before the scan, so changes during it show up next time.
+            let mut dirs = BTreeMap::new();
+            for path in &paths {
+                finder
+                    .record_dirs(path, finder.dir_depth(), &mut dirs)
+                    .await?;
+            }
+            let listing = finder.scan().await?;
+            (dirs, listing, finder.enrich_discovered(|_| true).await?)
+        };
+
+        Caches::new(
+            &*finder.workspace_cache.read().await,
+            &*finder.root_cache.read().await,
+        )
+        .save()?;
+        let index = Index::new(paths, depth, dirs, listing, projects, frecency::now());
+        index.save()?;
+
+        Ok(index)
+    }
+
+    /// Depth down to which directories are recorded in the index.
+    ///
+    /// Markers are found up to the search depth, so a new one changes the modification
+    /// time of a directory one level less deep.
+    const fn dir_depth(&self) -> usize {
+        self.config.depth.saturating_sub(1)
+    }
+
+    /// Record the modification times of a directory and of the directories up to
+    /// `depth` levels below it.
+    async fn record_dirs(
+        &self,
+        dir: &Path,
+        depth: usize,
+        dirs: &mut BTreeMap<PathBuf, SystemTime>,
+    ) -> Result<()> {
+        let mut found = vec![dir.to_path_buf()];
+        if depth > 0 {
+            found.extend(find_dirs(&self.deps, dir, depth).await?);
+        }
+
+        for (dir, modified) in modification_times(found).await {
+            if let Some(modified) = modified {
+                dirs.insert(dir, modified);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Seed the workspace and project root caches with the roots resolved by the scan for
+    /// the previous index.
+    async fn load_caches(&self) {
+        let (workspaces, roots) = Caches::load()
+            .unwrap_or_else(|e| {
+                warn!("Ignoring cached roots: {e}");
+                Caches::default()
+            })
+            .into_maps();
+        *self.workspace_cache.write().await = workspaces;
+        *self.root_cache.write().await = roots;
+    }
+
+    /// Bring the previous index of some of the configured paths up to date.
+    ///
+    /// Directories whose modification time is unchanged still contain the same
+    /// repositories and marker files, so only the changed ones are listed again and
+    /// only new directories are walked. Removed directories are dropped with everything
+    /// below them. Configured paths the previous index did not cover are scanned in
+    /// full.
+    ///
+    /// The workspace and project roots resolved by the previous scan, loaded by
+    /// [`Self::build_index`], are forgotten in changed and removed directories, and all
+    /// projects are then resolved from the updated listing. Projects with the same markers as before, outside of changed
+    /// and removed directories, keep their details from the previous index, the others
+    /// are filled in again.
+    async fn rescan(
+        &self,
+        previous: Index,
+    ) -> Result<(BTreeMap<PathBuf, SystemTime>, Listing, Vec<Project>)> {
+        let Index {
+            paths: indexed_paths,
+            mut dirs,
+            mut listing,
+            projects: indexed_projects,
+            ..
+        } = previous;
+
+        let (changed, removed) = changed_dirs(&dirs).await;
+        let mut changed = changed
+            .into_iter()
+            .map(|(dir, modified)| {
+                dirs.insert(dir.clone(), modified);
+                dir
+            })
+            .collect::<Vec<_>>();
+        for dir in &removed {
+            dirs.retain(|known, _| !known.starts_with(dir));
+            listing.remove_below(dir);
+        }
+        changed.retain(|dir| dirs.contains_key(dir));
+        self.forget_roots(&changed, &removed).await;
+
+        if self.config.verbose {
+            info!(
+                "Rescanning {} changed directories, {} removed",
+                changed.len(),
+                removed.len()
+            );
+        }
+
+        for dir in &changed {
+            listing.remove_in(dir);
+            listing.extend(self.list_directory(dir, 1).await?);
+
+            let level = self.depth_below_paths(dir);
+            if level >= self.dir_depth() {
+                continue;
+            }
+            for subdir in find_dirs(&self.deps, dir, 1).await? {
+                if dirs.contains_key(&subdir) {
+                    continue;
+                }
+                self.record_dirs(&subdir, self.dir_depth() - level - 1, &mut dirs)
+                    .await?;
+                listing.extend(
+                    self.list_directory(&subdir, self.config.depth - level - 1)
+                        .await?,
+                );
+            }
+        }
+
+        for path in &self.config.paths {
+            if !indexed_paths.contains(path) {
+                self.record_dirs(path, self.dir_depth(), &mut dirs).await?;
+                listing.extend(self.list_directory(path, self.config.depth).await?);
+            }
+        }
+
+        self.process_listing(&listing).await?;
+
+        let affected = |root: &Path| {
+            changed
+                .iter()
+                .chain(&removed)
+                .any(|dir| root.starts_with(dir))
+        };
+        let mut unaffected = indexed_projects
+            .into_iter()
+            .filter(|project| !affected(&project.root))
+            .map(|project| (project.root.clone(), project))
+            .collect::<HashMap<_, _>>();
+        let mut projects = Vec::new();
+        for project in self.discovered_projects.read().await.values() {
+            if let Some(indexed) = unaffected.remove(&project.root)
+                && indexed.markers == project.markers
+                && indexed.members == project.members
+            {
+                projects.push(indexed);
+            }
+        }
+
+        if self.config.verbose {
+            info!("Keeping the details of {} projects", projects.len());
+        }
+        let kept = projects
             .iter()
+            .map(|project| project.root.clone())
+            .collect::<HashSet<_>>();
+        projects.extend(
+            self.enrich_discovered(|project| !kept.contains(&project.root))
+                .await?,
+        );
+
+        Ok((dirs, listing, projects))
+    }
+
+    /// Forget the workspace and project roots resolved in or below directories that
+    /// changed or were removed, as their files may have changed.
+    async fn forget_roots(&self, changed: &[PathBuf], removed: &[PathBuf]) {
+        let in_changed = |dir: &Path| changed.iter().chain(removed).any(|c| dir.starts_with(c));
+
+        self.workspace_cache.write().await.retain(|dir, _| {
+            !changed.iter().any(|c| c == dir) && !removed.iter().any(|r| dir.starts_with(r))
+        });
+        self.root_cache
+            .write()
+            .await
+            .retain(|(dir, _), _| !in_changed(dir));
+    }
+
+    /// Projects discovered so far by a discovery running in another task that pass
+    /// `keep`, without their details and with only the marker files processed until now.
+    pub async fn discovered_so_far(&self, keep: impl Fn(&Project) -> bool) -> Vec<Project> {
+        self.discovered_projects
+            .read()
+            .await
+            .values()
+            .filter(|project| keep(project))
             .cloned()
-            .collect::<Vec<PathBuf>>();
-        projects.sort();
-        if self.config.max_results > 0 && projects.len() > self.config.max_results {
-            projects.truncate(self.config.max_results);
+            .collect()
+    }
+
+    /// Discover all Git repositories containing a project in the configured paths.
+    ///
+    /// Linked worktrees are represented by their main working tree, as they share their
+    /// branches and history with it.
+    ///
+    /// # Returns
+    ///
+    /// One project per repository root, sorted by root.
+    pub async fn find_repositories(&self) -> Result<Vec<Project>> {
+        let mut repos = BTreeSet::new();
+        for project in self.discover_projects().await? {
+            let main = match project.main_worktree {
+                Some(main) if is_repo_root(&main).await => Some(main),
+                _ => None,
+            };
+            repos.extend(main.or(project.git_root));
+        }
+
+        let mut projects = Vec::with_capacity(repos.len());
+        for repo in repos {
+            projects.push(self.describe_project(&repo).await?);
         }
 
         Ok(projects)
     }
 
-    /// Process a single directory by scanning for git repositories and marker files.
-    async fn process_directory(&self, dir: &Path) -> Result<()> {
-        // Look for git repositories first.
-        let git_repos = find_git_repos(&self.deps, dir, self.config.depth).await?;
+    /// Read the working tree status of the repositories containing the given projects.
+    ///
+    /// Repositories whose status cannot be read are left without one.
+    pub async fn read_git_statuses(&self, projects: &mut [Project]) -> Result<()> {
+        let statuses = self
+            .per_repository(projects, |deps, repo| async move {
+                let output = git_status(&deps, &repo).await?;
+                let mut status = parse_status(&output);
+                status.stashes = stash_count(&repo).await;
+                Ok(status)
+            })
+            .await?;
+
+        for project in projects {
+            project.status = project
+                .git_root
+                .as_ref()
+                .and_then(|repo| statuses.get(repo))
+                .cloned();
+        }
 
-        {
-            let mut projects = self.discovered_projects.write().await;
-            projects.extend(git_repos);
+        Ok(())
+    }
+
+    /// Read the local branches with commits missing from every remote of the
+    /// repositories containing the given projects, and whether they have a remote at all.
+    ///
+    /// Repositories whose branches cannot be read are left without a result.
+    pub async fn read_unpushed(&self, projects: &mut [Project]) -> Result<()> {
+        let unpushed = self
+            .per_repository(projects, |deps, repo| async move {
+                let branches = git_unpushed_branches(&deps, &repo).await?;
+                Ok(Unpushed {
+                    no_remote: read_remotes(&repo).await.is_empty(),
+                    branches: branches
+                        .into_iter()
+                        .map(|(branch, commits)| UnpushedBranch { branch, commits })
+                        .collect(),
+                })
+            })
+            .await?;
+
+        for project in projects {
+            project.unpushed = project
+                .git_root
+                .as_ref()
+                .and_then(|repo| unpushed.get(repo))
+                .cloned();
+        }
+
+        Ok(())
+    }
+
+    /// Read the root commits of the repositories containing the given projects.
+    ///
+    /// This walks the whole history of each repository. Repositories without commits or
+    /// whose history cannot be read are left without any.
+    pub async fn read_root_commits(&self, projects: &mut [Project]) -> Result<()> {
+        let root_commits = self
+            .per_repository(projects, |deps, repo| async move {
+                git_root_commits(&deps, &repo).await
+            })
+            .await?;
+
+        for project in projects {
+            project.root_commits = project
+                .git_root
+                .as_ref()
+                .and_then(|repo| root_commits.get(repo))
+                .cloned()
+                .unwrap_or_default();
+        }
+
+        Ok(())
+    }
+
+    /// Read the latest commit of the repositories containing the given projects.
+    ///
+    /// Repositories without commits are left without one.
+    pub async fn read_last_commits(&self, projects: &mut [Project]) -> Result<()> {
+        let last_commits = self
+            .per_repository(projects, |deps, repo| async move {
+                let (timestamp, date) = git_last_commit(&deps, &repo).await?;
+                Ok(LastCommit { timestamp, date })
+            })
+            .await?;
+
+        for project in projects {
+            project.last_commit = project
+                .git_root
+                .as_ref()
+                .and_then(|repo| last_commits.get(repo))
+                .cloned();
+        }
+
+        Ok(())
+    }
+
+    /// Run a `git` based task once for each repository containing one of the projects.
+    ///
+    /// At most `GIT_CONCURRENCY` tasks run at the same time. Failures are logged
+    /// and leave the repository out of the result.
+    async fn per_repository<T, F, Fut>(
+        &self,
+        projects: &[Project],
+        task: F,
+    ) -> Result<HashMap<PathBuf, T>>
+    where
+        T: Send + 'static,
+        F: Fn(Dependencies, PathBuf) -> Fut,
+        Fut: Future<Output = Result<T>> + Send + 'static,
+    {
+        // Fail early if git is missing instead of once per repository.
+        self.deps.git()?;
+
+        let repos = projects
+            .iter()
+            .filter_map(|project| project.git_root.clone())
+            .collect::<BTreeSet<_>>();
+
+        let semaphore = Arc::new(Semaphore::new(GIT_CONCURRENCY));
+        let handles = repos.into_iter().map(|repo| {
+            let semaphore = Arc::clone(&semaphore);
+            let future = task(self.deps.clone(), repo.clone());
+            spawn(async move {
+                let _permit = semaphore.acquire().await.ok()?;
+                match future.await {
+                    Ok(result) => Some((repo, result)),
+                    Err(e) => {
+                        warn!("Failed to read {}: {e}", repo.display());
+                        None
+                    }
+                }
+            })
+        });
+
+        Ok(join_all(handles)
+            .await
+            .into_iter()
+            .filter_map(|handle_result| handle_result.ok().flatten())
+            .collect())
+    }
+
+    /// Describe a single project root outside of a discovery run.
+    ///
+    /// Only the marker files directly inside `root` are taken into account.
+    pub async fn describe_project(&self, root: &Path) -> Result<Project> {
+        let mut project = Project::new(root.to_path_buf());
+        for marker in MARKER_PATTERNS {
+            let marker_path = root.join(marker);
+            if marker_path.is_file() {
+                project.add_marker(marker_path);
+            }
         }
 
-        // Look for marker files.
-        let marker_map = find_files(&self.deps, dir, &MARKER_PATTERNS, self.config.depth).await?;
-        for (pattern, paths) in marker_map {
-            for path in paths {
-                if let Some(parent_dir) = path.parent() {
-                    self.process_marker(parent_dir, &pattern).await?;
+        self.enrich_project(&mut project).await?;
+        Ok(project)
+    }
+
+    /// Express a path read from Git's own files relative to the search path containing
+    /// it, the same way discovered paths are, so they can be compared and printed
+    /// alongside them.
+    fn as_found(&self, path: &Path) -> PathBuf {
+        self.config
+            .paths
+            .iter()
+            .find_map(|search_path| {
+                let rest = path.strip_prefix(absolute(search_path).ok()?).ok()?;
+                if rest.as_os_str().is_empty() {
+                    return Some(search_path.clone());
                 }
+                Some(search_path.join(rest))
+            })
+            .unwrap_or_else(|| path.to_path_buf())
+    }
+
+    /// Depth of a directory below the closest search path containing it.
+    fn depth_below_paths(&self, dir: &Path) -> usize {
+        let Ok(dir) = absolute(dir) else {
+            return 0;
+        };
+        self.config
+            .paths
+            .iter()
+            .filter_map(|path| absolute(path).ok())
+            .filter_map(|path| Some(dir.strip_prefix(path).ok()?.components().count()))
+            .min()
+            .unwrap_or_default()
+    }
+
+    /// Fill in the details of a project that depend on its surroundings: its depth below
+    /// the search path, the enclosing workspace, the containing repository and its Git
+    /// state, and the metadata declared in its manifests.
+    pub async fn enrich_project(&self, project: &mut Project) -> Result<()> {
+        project.metadata = read_metadata(&project.root).await;
+
+        project.depth = self.depth_below_paths(&project.root);
+
+        project.vcs_root = closest_ancestor(&project.root, is_vcs_root).await;
+        project.vcs = match &project.vcs_root {
+            Some(vcs_root) => vcs::detect(vcs_root).await,
+            None => BTreeSet::new(),
+        };
+
+        project.git_root = closest_ancestor(&project.root, is_repo_root).await;
+        project.bare = is_bare(&project.root).await;
+        if let Some(git_root) = &project.git_root {
+            project.git = read_git_info(git_root).await;
+            project.remotes = read_remotes(git_root).await;
+            project.superproject = superproject(git_root).await;
+            project.submodules = read_submodules(git_root).await;
+            project.main_worktree = main_worktree(git_root)
+                .await
+                .map(|main| self.as_found(&main));
+            project.worktrees = linked_worktrees(git_root)
+                .await
+                .iter()
+                .map(|worktree| self.as_found(worktree))
+                .collect();
+        }
+
+        for dir in project.root.ancestors().skip(1) {
+            if dir.as_os_str().is_empty() {
+                break;
+            }
+
+            if self.is_workspace_root(dir).await? {
+                project.workspace_parent = Some(dir.to_path_buf());
+                break;
             }
         }
 
         Ok(())
     }
 
-    /// Process a marker file found in a directory.
-    async fn process_marker(&self, dir: &Path, marker_name: &str) -> Result<()> {
-        // Determine marker type
-        let marker_type = marker_name.parse().expect("How did we get here?");
+    /// Resolve the project root that owns the given file or directory.
+    ///
+    /// Starting at `path` (or its nearest existing ancestor), walks upwards until a
+    /// directory that is a repository root or contains one of the marker files is found. The root is then
+    /// resolved with the same rules used during discovery, unless `members` is set, in
+    /// which case the directory holding the marker itself is returned instead of its
+    /// workspace root.
+    ///
+    /// # Returns
+    ///
+    /// The project root, or `None` if no ancestor looks like a project.
+    pub async fn resolve_root(&self, path: &Path, members: bool) -> Result<Option<PathBuf>> {
+        let Some(start) = path.ancestors().find(|ancestor| ancestor.is_dir()) else {
+            return Ok(None);
+        };
 
-        // Find project root
-        let project_root = self.find_project_root(dir, &marker_type).await?;
+        for dir in start.ancestors() {
+            if dir.as_os_str().is_empty() {
+                break;
+            }
 
-        // Improved nested project detection
-        // Only ignore if it's a subproject of the same type (prevents ignoring
-        // valid nested projects of different types)
-        let mut should_add = true;
-        {
-            let projects = self.discovered_projects.read().await;
-            for known_project in projects.iter() {
-                // Check if this is a direct parent (not just any ancestor)
-                let is_direct_parent = project_root
-                    .parent()
-                    .is_some_and(|parent| parent == known_project);
-
-                // Only exclude if it's a subdirectory and has the same marker type
-                // or if it's exactly the same directory
-                if project_root == *known_project
-                    || project_root.starts_with(known_project) && !is_direct_parent
-                {
-                    should_add = false;
-                    break;
+            if is_vcs_root(dir).await {
+                return Ok(Some(dir.to_path_buf()));
+            }
+
+            let marker = MARKER_PATTERNS
+                .iter()
+                .find(|marker| dir.join(marker).is_file());
+            if let Some(marker) = marker {
+                if members {
+                    return Ok(Some(dir.to_path_buf()));
                 }
+
+                let Ok(marker_type) = marker.parse::<MarkerType>();
+                let root = self.find_project_root(dir, &marker_type).await?;
+                return Ok(Some(root));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Scan a single directory for repositories and marker files, up to `depth` levels
+    /// below it.
+    async fn list_directory(&self, dir: &Path, depth: usize) -> Result<Listing> {
+        let repos = find_repos(&self.deps, dir, depth).await?;
+        let bare_repos = if self.config.include_bare {
+            find_bare_repos(&self.deps, dir, depth).await?
+        } else {
+            Vec::new()
+        };
+        let markers = find_files(&self.deps, dir, &MARKER_PATTERNS, depth).await?;
+
+        Ok(Listing {
+            repos: repos.into_iter().collect(),
+            bare_repos: bare_repos.into_iter().collect(),
+            markers: markers.into_values().flatten().collect(),
+        })
+    }
+
+    /// Turn the repositories and marker files found by a scan into projects.
+    async fn process_listing(&self, listing: &Listing) -> Result<()> {
+        // Repositories come first, as they own the markers inside them.
+        {
+            let mut projects = self.discovered_projects.write().await;
+            for repo in listing.repos.iter().chain(&listing.bare_repos) {
+                projects
+                    .entry(repo.clone())
+                    .or_insert_with(|| Project::new(repo.clone()));
+            }
+        }
+
+        // Every marker is resolved to its root before any is given an owner, which
+        // happens from the shallowest root down so the projects enclosing a root are
+        // known by the time it is processed, whatever order the markers were listed in.
+        let mut resolved = Vec::with_capacity(listing.markers.len());
+        for marker in &listing.markers {
+            if let (Some(parent_dir), Some(name)) = (marker.parent(), marker.file_name()) {
+                let Ok(marker_type) = name.to_string_lossy().parse::<MarkerType>();
+                let project_root = self.find_project_root(parent_dir, &marker_type).await?;
+                resolved.push((project_root, marker.clone()));
             }
         }
+        resolved.sort_by_key(|(project_root, _)| project_root.components().count());
 
-        if should_add {
-            self.discovered_projects.write().await.insert(project_root);
+        for (project_root, marker) in resolved {
+            self.process_marker(project_root, marker).await;
         }
 
         Ok(())
     }
 
+    /// Give a marker file resolved to `project_root` to the project owning it.
+    async fn process_marker(&self, project_root: PathBuf, marker: PathBuf) {
+        // Improved nested project detection
+        // Only ignore if it's a subproject of the same type (prevents ignoring
+        // valid nested projects of different types). Ignored subprojects become
+        // members of the project they belong to.
+        let owner = {
+            let projects = self.discovered_projects.read().await;
+            // A root that is already known, such as a nested repository, owns its
+            // markers. Otherwise the deepest enclosing project owns them.
+            let enclosing = projects
+                .keys()
+                .filter(|known_project| {
+                    // Check if this is a direct parent (not just any ancestor)
+                    let is_direct_parent = project_root
+                        .parent()
+                        .is_some_and(|parent| parent == *known_project);
+
+                    project_root.starts_with(known_project) && !is_direct_parent
+                })
+                .max_by_key(|known_project| known_project.components().count())
+                .filter(|_| !projects.contains_key(&project_root))
+                .cloned();
+            drop(projects);
+            enclosing.unwrap_or(project_root)
+        };
+
+        self.discovered_projects
+            .write()
+            .await
+            .entry(owner.clone())
+            .or_insert_with(|| Project::new(owner))
+            .add_marker(marker);
+    }
+
     async fn find_project_root(&self, dir: &Path, marker_type: &MarkerType) -> Result<PathBuf> {
         // Check cache
         let cache_key = (dir.to_path_buf(), format!("{marker_type:?}"));
@@ -204,6 +1082,12 @@ impl ProjectFinder {
             }
         }
 
+        // Markers at the root of a repository, such as a submodule, belong to it rather
+        // than to an enclosing project.
+        if is_vcs_root(dir).await {
+            return Ok(dir.to_path_buf());
+        }
+
         let mut result = dir.to_path_buf();
 
         match marker_type {
@@ -220,7 +1104,7 @@ impl ProjectFinder {
                         break;
                     }
 
-                    if parent.join(".git").is_dir() {
+                    if is_vcs_root(parent).await {
                         result = parent.to_path_buf();
                         break;
                     }
@@ -245,7 +1129,7 @@ impl ProjectFinder {
                         break;
                     }
 
-                    if parent.join(".git").is_dir() {
+                    if is_vcs_root(parent).await {
                         result = parent.to_path_buf();
                         break;
                     }
@@ -255,7 +1139,7 @@ impl ProjectFinder {
             }
 
             MarkerType::BuildFile(name) => {
-                // For build system files, find the highest one that's still in the same git repo
+                // For build system files, find the highest one that's still in the same repo
                 let mut highest_dir = dir.to_path_buf();
                 let mut current = dir.to_path_buf();
 
@@ -268,7 +1152,7 @@ impl ProjectFinder {
                         highest_dir = parent.to_path_buf();
                     }
 
-                    if parent.join(".git").is_dir() {
+                    if is_vcs_root(parent).await {
                         result = parent.to_path_buf();
                         break;
                     }
@@ -282,14 +1166,14 @@ impl ProjectFinder {
             }
 
             MarkerType::OtherConfig(_) => {
-                // For other file types, just look for git repos
+                // For other file types, just look for repos
                 let mut current = dir.to_path_buf();
                 while let Some(parent) = current.parent() {
                     if parent.as_os_str().is_empty() {
                         break;
                     }
 
-                    if parent.join(".git").is_dir() {
+                    if is_vcs_root(parent).await {
                         result = parent.to_path_buf();
                         break;
                     }
@@ -0,0 +1,418 @@
+use crate::{
+    dirs::data_dir,
+    errors::{ProjectFinderError, Result},
+    project::Project,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs::{self, File, OpenOptions, canonicalize},
+    io::ErrorKind,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// Sum of all ranks above which the database is aged, as in zoxide.
+const MAX_RANK: f64 = 10_000.0;
+
+const HOUR: u64 = 60 * 60;
+const DAY: u64 = 24 * HOUR;
+const WEEK: u64 = 7 * DAY;
+
+/// A directory recorded by `visit`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Visit {
+    pub path: PathBuf,
+    /// Number of visits, scaled down whenever the database is aged.
+    pub rank: f64,
+    /// Time of the latest visit, in seconds since the Unix epoch.
+    pub last_visited: u64,
+}
+
+impl Visit {
+    /// The rank weighted by how recently the directory was visited.
+    pub fn score(&self, now: u64) -> f64 {
+        let elapsed = now.saturating_sub(self.last_visited);
+        let weight = if elapsed < HOUR {
+            4.0
+        } else if elapsed < DAY {
+            2.0
+        } else if elapsed < WEEK {
+            0.5
+        } else {
+            0.25
+        };
+        self.rank * weight
+    }
+
+    /// Check whether the path matches all keywords, case-insensitively.
+    ///
+    /// As in zoxide, the keywords have to appear in order and the last one has to match
+    /// the last component of the path.
+    fn matches(&self, keywords: &[String]) -> bool {
+        let path = self.path.to_string_lossy().to_lowercase();
+        let mut rest = path.as_str();
+        for keyword in keywords {
+            let keyword = keyword.to_lowercase();
+            match rest.find(&keyword) {
+                Some(start) => rest = &rest[start + keyword.len()..],
+                None => return false,
+            }
+        }
+
+        keywords.last().is_none_or(|keyword| {
+            let name = self
+                .path
+                .file_name()
+                .map(|name| name.to_string_lossy().to_lowercase())
+                .unwrap_or_default();
+            name.contains(&keyword.to_lowercase())
+        })
+    }
+}
+
+/// On-disk format of the database.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Document {
+    visits: Vec<Visit>,
+}
+
+/// Visited directories, stored in `visits.json` in the data directory.
+#[derive(Debug)]
+pub struct Database {
+    path: PathBuf,
+    visits: Vec<Visit>,
+    /// Exclusive lock on `visits.lock`, held from opening the database for an update
+    /// until it is dropped.
+    _lock: Option<File>,
+}
+
+impl Database {
+    /// Open the database, which is empty if it was never written.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the data directory cannot be determined or the database
+    /// cannot be read or parsed.
+    pub fn open() -> Result<Self> {
+        Self::read(data_dir()?.join("visits.json"), None)
+    }
+
+    /// Open the database to change and save it.
+    ///
+    /// Other processes updating the database wait until this one is dropped, so
+    /// concurrent visits from several shells are all recorded.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the data directory cannot be created, the lock cannot be
+    /// taken or the database cannot be read or parsed.
+    pub fn open_for_update() -> Result<Self> {
+        Self::open_for_update_in(&data_dir()?)
+    }
+
+    /// Open the database in `dir` to change and save it, see [`Self::open_for_update`].
+    fn open_for_update_in(dir: &Path) -> Result<Self> {
+        fs::create_dir_all(dir)?;
+
+        let lock = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(dir.join("visits.lock"))?;
+        lock.lock()?;
+
+        Self::read(dir.join("visits.json"), Some(lock))
+    }
+
+    fn read(path: PathBuf, lock: Option<File>) -> Result<Self> {
+        let visits = match fs::read_to_string(&path) {
+            Ok(contents) => {
+                serde_json::from_str::<Document>(&contents)
+                    .map_err(|e| {
+                        ProjectFinderError::DatabaseParseFailed(path.clone(), e.to_string())
+                    })?
+                    .visits
+            }
+            Err(e) if e.kind() == ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(e.into()),
+        };
+
+        Ok(Self {
+            path,
+            visits,
+            _lock: lock,
+        })
+    }
+
+    /// Write the database, replacing the previous one atomically.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the data directory cannot be created or written to.
+    pub fn save(&self) -> Result<()> {
+        if let Some(dir) = self.path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+
+        let document = Document {
+            visits: self.visits.clone(),
+        };
+        let contents = serde_json::to_string(&document).map_err(|e| {
+            ProjectFinderError::DatabaseParseFailed(self.path.clone(), e.to_string())
+        })?;
+
+        let temporary = self
+            .path
+            .with_extension(format!("json.{}", std::process::id()));
+        fs::write(&temporary, contents)?;
+        fs::rename(&temporary, &self.path)?;
+        Ok(())
+    }
+
+    /// Record a visit of a directory.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the directory does not exist.
+    pub fn visit(&mut self, dir: &Path, now: u64) -> Result<()> {
+        let path = canonicalize(dir).map_err(|_| ProjectFinderError::PathNotFound(dir.into()))?;
+
+        match self.visits.iter_mut().find(|visit| visit.path == path) {
+            Some(visit) => {
+                visit.rank += 1.0;
+                visit.last_visited = now;
+            }
+            None => self.visits.push(Visit {
+                path,
+                rank: 1.0,
+                last_visited: now,
+            }),
+        }
+
+        self.age();
+        Ok(())
+    }
+
+    /// Scale all ranks down once their sum exceeds [`MAX_RANK`], forgetting the
+    /// directories that drop below a single visit.
+    fn age(&mut self) {
+        let total = self.visits.iter().map(|visit| visit.rank).sum::<f64>();
+        if total <= MAX_RANK {
+            return;
+        }
+
+        let factor = 0.9 * MAX_RANK / total;
+        for visit in &mut self.visits {
+            visit.rank *= factor;
+        }
+        self.visits.retain(|visit| visit.rank >= 1.0);
+    }
+
+    /// Find the existing directory with the highest score matching all keywords.
+    pub fn jump(&self, keywords: &[String], now: u64) -> Option<&Visit> {
+        self.visits
+            .iter()
+            .filter(|visit| visit.matches(keywords) && visit.path.is_dir())
+            .max_by(|a, b| a.score(now).total_cmp(&b.score(now)))
+    }
+
+    /// Set the frecency of each project to the sum of the scores of the directories
+    /// visited in it.
+    ///
+    /// A visit counts towards the innermost project containing it. Projects that were
+    /// never visited keep no frecency.
+    pub fn score_projects(&self, projects: &mut [Project], now: u64) {
+        let roots = projects
+            .iter()
+            .map(|project| canonicalize(&project.root).ok())
+            .collect::<Vec<_>>();
+
+        for visit in &self.visits {
+            let innermost = roots
+                .iter()
+                .enumerate()
+                .filter_map(|(index, root)| Some((index, root.as_ref()?)))
+                .filter(|(_, root)| visit.path.starts_with(root))
+                .max_by_key(|(_, root)| root.components().count());
+
+            if let Some((index, _)) = innermost {
+                *projects[index].frecency.get_or_insert(0.0) += visit.score(now);
+            }
+        }
+    }
+}
+
+/// Current time in seconds since the Unix epoch.
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{fs::TryLockError, io};
+    use tempfile::tempdir;
+
+    const NOW: u64 = 100 * WEEK;
+
+    fn visit(path: &str, rank: f64, last_visited: u64) -> Visit {
+        Visit {
+            path: PathBuf::from(path),
+            rank,
+            last_visited,
+        }
+    }
+
+    fn database(visits: Vec<Visit>) -> Database {
+        Database {
+            path: PathBuf::from("visits.json"),
+            visits,
+            _lock: None,
+        }
+    }
+
+    fn keywords(keywords: &[&str]) -> Vec<String> {
+        keywords.iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn recent_visits_weigh_more() {
+        let score = |elapsed| visit("/code/app", 2.0, NOW - elapsed).score(NOW);
+
+        assert!((score(0) - 8.0).abs() < f64::EPSILON);
+        assert!((score(2 * HOUR) - 4.0).abs() < f64::EPSILON);
+        assert!((score(2 * DAY) - 1.0).abs() < f64::EPSILON);
+        assert!((score(2 * WEEK) - 0.5).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn aging_rescales_and_forgets_rare_visits() {
+        let mut database = database(vec![
+            visit("/code/app", 9_000.0, NOW),
+            visit("/code/api", 1_500.0, NOW),
+            visit("/code/old", 1.05, NOW),
+        ]);
+        database.age();
+
+        let total = database.visits.iter().map(|visit| visit.rank).sum::<f64>();
+        let expected = 0.9 * MAX_RANK;
+        assert!((total - expected).abs() < 1.0);
+        let paths = database
+            .visits
+            .iter()
+            .map(|visit| visit.path.as_path())
+            .collect::<Vec<_>>();
+        assert_eq!(paths, [Path::new("/code/app"), Path::new("/code/api")]);
+    }
+
+    #[test]
+    fn aging_waits_for_the_maximum_rank() {
+        let mut database = database(vec![visit("/code/app", MAX_RANK, NOW)]);
+        database.age();
+
+        assert!((database.visits[0].rank - MAX_RANK).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn keywords_match_in_order_and_last_one_in_the_name() {
+        let visit = visit("/home/ada/Code/api-server", 1.0, NOW);
+
+        assert!(visit.matches(&keywords(&["code", "API"])));
+        assert!(visit.matches(&keywords(&["server"])));
+        assert!(visit.matches(&[]));
+        assert!(!visit.matches(&keywords(&["api", "code"])));
+        // Only the last keyword has to be in the final component.
+        assert!(!visit.matches(&keywords(&["code"])));
+    }
+
+    #[test]
+    fn jump_prefers_the_best_existing_directory() -> io::Result<()> {
+        let dir = tempdir()?;
+        let (often, recent) = (dir.path().join("app-old"), dir.path().join("app-new"));
+        fs::create_dir(&often)?;
+        fs::create_dir(&recent)?;
+
+        let gone = dir.path().join("app-gone");
+        let database = database(vec![
+            Visit {
+                path: often.clone(),
+                rank: 10.0,
+                last_visited: NOW - 2 * WEEK,
+            },
+            Visit {
+                path: recent.clone(),
+                rank: 1.0,
+                last_visited: NOW,
+            },
+            Visit {
+                path: gone,
+                rank: 100.0,
+                last_visited: NOW,
+            },
+        ]);
+
+        let best = database
+            .jump(&keywords(&["app"]), NOW)
+            .map(|visit| &visit.path);
+        assert_eq!(best, Some(&recent));
+        let best = database
+            .jump(&keywords(&["old"]), NOW)
+            .map(|visit| &visit.path);
+        assert_eq!(best, Some(&often));
+        Ok(())
+    }
+
+    #[test]
+    fn visits_count_towards_the_innermost_project() -> io::Result<()> {
+        let dir = tempdir()?;
+        let root = canonicalize(dir.path())?;
+        let (outer, inner, other) = (root.join("mono"), root.join("mono/web"), root.join("tool"));
+        fs::create_dir_all(inner.join("src"))?;
+        fs::create_dir(&other)?;
+
+        let database = database(vec![
+            Visit {
+                path: outer.clone(),
+                rank: 1.0,
+                last_visited: NOW,
+            },
+            Visit {
+                path: inner.join("src"),
+                rank: 2.0,
+                last_visited: NOW,
+            },
+        ]);
+        let mut projects = [outer, inner, other].map(Project::new);
+        database.score_projects(&mut projects, NOW);
+
+        let frecency = projects.map(|project| project.frecency);
+        assert_eq!(frecency, [Some(4.0), Some(8.0), None]);
+        Ok(())
+    }
+
+    #[test]
+    fn updates_hold_the_lock_until_dropped() -> Result<()> {
+        let dir = tempdir()?;
+        let lock = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(dir.path().join("visits.lock"))?;
+
+        let mut database = Database::open_for_update_in(dir.path())?;
+        assert!(matches!(lock.try_lock(), Err(TryLockError::WouldBlock)));
+
+        database.visit(dir.path(), NOW)?;
+        database.save()?;
+        drop(database);
+        assert!(lock.try_lock().is_ok());
+        lock.unlock()?;
+
+        // The next update sees the visit saved by the previous one.
+        let database = Database::open_for_update_in(dir.path())?;
+        assert_eq!(database.visits.len(), 1);
+        Ok(())
+    }
+}
@@ -0,0 +1,296 @@
+use crate::{errors::Result, finder::ProjectFinder, project::Project};
+use std::path::Path;
+
+/// Score of every matched character.
+const SCORE_MATCH: i64 = 16;
+/// Penalty for the first character skipped between two matched characters.
+const PENALTY_GAP_START: i64 = 3;
+/// Penalty for every further character skipped.
+const PENALTY_GAP_EXTENSION: i64 = 1;
+/// Bonus for a match right after the previous one.
+const BONUS_CONSECUTIVE: i64 = 4;
+/// Bonus for a match at the start of a word, after a separator such as `/` or `-`.
+const BONUS_BOUNDARY: i64 = 8;
+/// Bonus for an upper case match following a lower case character.
+const BONUS_CAMEL_CASE: i64 = 7;
+/// Bonus for matches in the name of the project directory rather than its parents.
+const BONUS_BASENAME: i64 = 32;
+
+/// Where and how well a pattern matched a text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FuzzyMatch {
+    pub score: i64,
+    /// Indices of the matched characters in the text.
+    pub positions: Vec<usize>,
+}
+
+/// Match a pattern against a text, requiring its characters to appear in order.
+///
+/// Matching is case-insensitive unless the pattern contains upper case characters. Of
+/// all the ways the pattern matches, the one with the highest score is returned: each
+/// matched character scores, with bonuses for consecutive matches and matches at word
+/// boundaries, and every gap between matches is penalized.
+pub fn fuzzy_match(pattern: &str, text: &str) -> Option<FuzzyMatch> {
+    let case_sensitive = pattern.chars().any(char::is_uppercase);
+    let fold = |c: char| {
+        if case_sensitive {
+            c
+        } else {
+            c.to_lowercase().next().unwrap_or(c)
+        }
+    };
+
+    let pattern = pattern.chars().map(fold).collect::<Vec<_>>();
+    let text = text.chars().collect::<Vec<_>>();
+    if pattern.is_empty() {
+        return Some(FuzzyMatch {
+            score: 0,
+            positions: Vec::new(),
+        });
+    }
+    if pattern.len() > text.len() {
+        return None;
+    }
+
+    let bonuses = (0..text.len())
+        .map(|j| match j.checked_sub(1).map(|i| text[i]) {
+            None => BONUS_BOUNDARY,
+            Some(previous) if !previous.is_alphanumeric() => BONUS_BOUNDARY,
+            Some(previous) if previous.is_lowercase() && text[j].is_uppercase() => BONUS_CAMEL_CASE,
+            Some(_) => 0,
+        })
+        .collect::<Vec<_>>();
+
+    // `scores[i][j]` is the best score of matching `pattern[..=i]` with `pattern[i]`
+    // at `text[j]`, `previous[i][j]` where `pattern[i - 1]` was matched then.
+    let mut scores = vec![vec![None::<i64>; text.len()]; pattern.len()];
+    let mut previous = vec![vec![0; text.len()]; pattern.len()];
+    for (j, &c) in text.iter().enumerate() {
+        if fold(c) == pattern[0] {
+            scores[0][j] = Some(SCORE_MATCH + bonuses[j]);
+        }
+    }
+
+    for i in 1..pattern.len() {
+        // Best score of the previous character matched before `j - 1`, with the gap
+        // up to `j` already penalized.
+        let mut gapped = None::<(i64, usize)>;
+        for j in i..text.len() {
+            if j >= 2
+                && let Some(score) = scores[i - 1][j - 2]
+            {
+                let opened = score - PENALTY_GAP_START;
+                let extended = gapped.map(|(score, k)| (score - PENALTY_GAP_EXTENSION, k));
+                gapped = match extended {
+                    Some(extended) if extended.0 >= opened => Some(extended),
+                    _ => Some((opened, j - 2)),
+                };
+            } else {
+                gapped = gapped.map(|(score, k)| (score - PENALTY_GAP_EXTENSION, k));
+            }
+
+            if fold(text[j]) != pattern[i] {
+                continue;
+            }
+
+            let adjacent = scores[i - 1][j - 1].map(|score| (score + BONUS_CONSECUTIVE, j - 1));
+            let best = match (adjacent, gapped) {
+                (Some(adjacent), Some(gapped)) if gapped.0 > adjacent.0 => Some(gapped),
+                (Some(adjacent), _) => Some(adjacent),
+                (None, gapped) => gapped,
+            };
+            if let Some((score, k)) = best {
+                scores[i][j] = Some(score + SCORE_MATCH + bonuses[j]);
+                previous[i][j] = k;
+            }
+        }
+    }
+
+    let last = pattern.len() - 1;
+    let (mut j, score) = scores[last]
+        .iter()
+        .enumerate()
+        .filter_map(|(j, score)| Some((j, (*score)?)))
+        .max_by_key(|&(j, score)| (score, std::cmp::Reverse(j)))?;
+
+    let mut positions = vec![0; pattern.len()];
+    for i in (0..pattern.len()).rev() {
+        positions[i] = j;
+        j = previous[i][j];
+    }
+
+    Some(FuzzyMatch { score, positions })
+}
+
+/// A project matching a query.
+#[derive(Debug, Clone)]
+pub struct Hit {
+    pub project: Project,
+    pub score: i64,
+    /// Indices of the characters of the project root that matched.
+    pub positions: Vec<usize>,
+}
+
+/// Match every term of a query against a project.
+///
+/// Each term is matched against the name of the project directory, its whole root path
+/// and the name and description from its manifests, and the best of these counts.
+/// Matches in the directory name get a bonus. The project matches if every term does.
+pub fn match_project(terms: &[&str], project: &Project) -> Option<(i64, Vec<usize>)> {
+    let root = project.root.to_string_lossy();
+    let basename_offset = basename_offset(&project.root);
+    let name = project.root.file_name().map(|name| name.to_string_lossy());
+    let fields = [
+        project.metadata.name.as_deref(),
+        project.metadata.description.as_deref(),
+    ];
+
+    let mut score = 0;
+    let mut positions = Vec::new();
+    for term in terms {
+        let in_name = name
+            .as_deref()
+            .zip(basename_offset)
+            .and_then(|(name, offset)| {
+                let found = fuzzy_match(term, name)?;
+                let positions = found.positions.iter().map(|i| i + offset).collect();
+                Some((found.score + BONUS_BASENAME, positions))
+            });
+        let in_root = fuzzy_match(term, &root).map(|found| (found.score, found.positions));
+        let in_metadata = fields
+            .iter()
+            .filter_map(|field| fuzzy_match(term, (*field)?))
+            .map(|found| (found.score, Vec::new()))
+            .max_by_key(|(score, _)| *score);
+
+        let (term_score, term_positions) = [in_name, in_root, in_metadata]
+            .into_iter()
+            .flatten()
+            .max_by_key(|(score, _)| *score)?;
+        score += term_score;
+        positions.extend(term_positions);
+    }
+
+    positions.sort_unstable();
+    positions.dedup();
+    Some((score, positions))
+}
+
+/// Index of the first character of the last component of a path, as a string.
+fn basename_offset(path: &Path) -> Option<usize> {
+    let root = path.to_string_lossy();
+    let name = path.file_name()?.to_string_lossy();
+    let trimmed = root.trim_end_matches(std::path::is_separator);
+    trimmed
+        .ends_with(name.as_ref())
+        .then(|| trimmed.chars().count() - name.chars().count())
+}
+
+/// Find the projects matching a query of whitespace separated terms, best first.
+///
+/// Projects are discovered and filtered like a normal search. Matches with the same
+/// score keep the order given by `--sort`.
+pub async fn find_matches(finder: &ProjectFinder, query: &str) -> Result<Vec<Hit>> {
+    let terms = query.split_whitespace().collect::<Vec<_>>();
+
+    let mut projects = finder.select_projects().await?;
+    finder.sort_projects(&mut projects).await?;
+
+    let mut hits = projects
+        .into_iter()
+        .filter_map(|project| {
+            let (score, positions) = match_project(&terms, &project)?;
+            Some(Hit {
+                project,
+                score,
+                positions,
+            })
+        })
+        .collect::<Vec<_>>();
+    hits.sort_by_key(|hit| std::cmp::Reverse(hit.score));
+
+    Ok(hits)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn positions(pattern: &str, text: &str) -> Option<Vec<usize>> {
+        fuzzy_match(pattern, text).map(|found| found.positions)
+    }
+
+    fn project(root: &str, description: Option<&str>) -> Project {
+        let mut project = Project::new(PathBuf::from(root));
+        project.metadata.description = description.map(str::to_string);
+        project
+    }
+
+    #[test]
+    fn contiguous_match_beats_gapped_match() {
+        let (Some(contiguous), Some(gapped)) =
+            (fuzzy_match("abc", "xabcx"), fuzzy_match("abc", "xaxbxc"))
+        else {
+            panic!("both texts contain the pattern");
+        };
+        assert!(contiguous.score > gapped.score);
+    }
+
+    #[test]
+    fn returns_positions_of_best_match() {
+        assert_eq!(positions("fb", "foo-bar"), Some(vec![0, 4]));
+        // The occurrence after a separator gets the boundary bonus.
+        assert_eq!(positions("bar", "foobar-bar"), Some(vec![7, 8, 9]));
+        assert_eq!(positions("abc", "acb"), None);
+    }
+
+    #[test]
+    fn smart_case() {
+        assert!(fuzzy_match("api", "MyAPI").is_some());
+        assert!(fuzzy_match("Api", "myapi").is_none());
+        assert_eq!(positions("Api", "api-Api"), Some(vec![4, 5, 6]));
+    }
+
+    #[test]
+    fn positions_count_characters_not_bytes() {
+        assert_eq!(positions("über", "grüße über"), Some(vec![6, 7, 8, 9]));
+        assert!(fuzzy_match("ü", "Ü").is_some());
+        assert!(fuzzy_match("ß", "strasse").is_none());
+    }
+
+    #[test]
+    fn pattern_longer_than_text() {
+        assert_eq!(fuzzy_match("abcd", "abc"), None);
+        assert_eq!(
+            fuzzy_match("", "abc"),
+            Some(FuzzyMatch {
+                score: 0,
+                positions: Vec::new(),
+            })
+        );
+    }
+
+    #[test]
+    fn match_in_basename_gets_bonus() {
+        let (Some((in_basename, positions)), Some((in_parent, _))) = (
+            match_project(&["api"], &project("/src/web/api", None)),
+            match_project(&["api"], &project("/src/api/web", None)),
+        ) else {
+            panic!("both roots contain the term");
+        };
+        assert!(in_basename > in_parent);
+        assert_eq!(positions, vec![9, 10, 11]);
+    }
+
+    #[test]
+    fn every_term_has_to_match() {
+        let project = project("/src/web/api", Some("Billing service"));
+        // Matches in the description have no positions in the root.
+        assert_eq!(
+            match_project(&["api", "billing"], &project).map(|(_, positions)| positions),
+            Some(vec![9, 10, 11])
+        );
+        assert!(match_project(&["api", "missing"], &project).is_none());
+    }
+}
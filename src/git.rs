@@ -0,0 +1,792 @@
+use serde::{Deserialize, Serialize};
+use std::{
+    fmt::{self, Display},
+    path::{Component, Path, PathBuf},
+};
+use tokio::fs::{metadata, read_dir, read_to_string};
+
+/// Number of hex digits shown for abbreviated commit hashes.
+const SHORT_HASH_LEN: usize = 7;
+
+/// An operation left in progress in a repository.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum GitOperation {
+    Merge,
+    Rebase,
+    Am,
+    CherryPick,
+    Revert,
+    Bisect,
+}
+
+impl GitOperation {
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Merge => "merge",
+            Self::Rebase => "rebase",
+            Self::Am => "am",
+            Self::CherryPick => "cherry-pick",
+            Self::Revert => "revert",
+            Self::Bisect => "bisect",
+        }
+    }
+}
+
+/// State of a Git repository, read directly from its `.git` directory.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GitInfo {
+    /// Current branch, or the branch being rebased while `HEAD` is detached.
+    pub branch: Option<String>,
+    /// Abbreviated hash of the commit `HEAD` points to, `None` on an unborn branch.
+    pub head: Option<String>,
+    /// Whether `HEAD` points directly to a commit instead of a branch.
+    pub detached: bool,
+    /// Merge, rebase or similar operation in progress.
+    pub operation: Option<GitOperation>,
+}
+
+/// Locate the Git directory of a repository root.
+///
+/// `.git` is either the Git directory itself or, in linked worktrees and submodule
+/// checkouts, a file pointing to it with a `gitdir: <path>` line. Bare repositories are
+/// their own Git directory.
+pub async fn git_dir(repo_root: &Path) -> Option<PathBuf> {
+    let dot_git = repo_root.join(".git");
+    if is_dir(&dot_git).await {
+        return Some(dot_git);
+    }
+    if is_bare(repo_root).await {
+        return Some(repo_root.to_path_buf());
+    }
+
+    let contents = read_to_string(&dot_git).await.ok()?;
+    let target = contents.lines().next()?.strip_prefix("gitdir:")?.trim();
+    let git_dir = normalize(&repo_root.join(target));
+    is_dir(&git_dir).await.then_some(git_dir)
+}
+
+/// Check whether a directory is the root of a Git working tree or a bare repository.
+pub async fn is_repo_root(dir: &Path) -> bool {
+    git_dir(dir).await.is_some()
+}
+
+/// Check whether a directory is a bare repository, recognized by its `HEAD`, `objects`
+/// and `refs`.
+///
+/// The Git directories of non-bare repositories share that structure, so anything
+/// inside a `.git` directory is not considered bare.
+pub async fn is_bare(dir: &Path) -> bool {
+    let in_git_dir = dir
+        .components()
+        .any(|component| component.as_os_str() == ".git");
+
+    !in_git_dir
+        && is_file(&dir.join("HEAD")).await
+        && is_dir(&dir.join("objects")).await
+        && is_dir(&dir.join("refs")).await
+}
+
+/// Check whether a path is a directory, following symbolic links.
+async fn is_dir(path: &Path) -> bool {
+    metadata(path).await.is_ok_and(|metadata| metadata.is_dir())
+}
+
+/// Check whether a path is a file, following symbolic links.
+async fn is_file(path: &Path) -> bool {
+    metadata(path)
+        .await
+        .is_ok_and(|metadata| metadata.is_file())
+}
+
+/// Check whether a path exists, following symbolic links.
+async fn exists(path: &Path) -> bool {
+    metadata(path).await.is_ok()
+}
+
+/// Resolve `.` and `..` components without touching the file system.
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir if normalized.file_name().is_some() => {
+                normalized.pop();
+            }
+            component => normalized.push(component),
+        }
+    }
+    normalized
+}
+
+/// Locate the directory holding the state shared by all worktrees of a repository:
+/// branches, packed references, the stash and the configuration.
+///
+/// Linked worktrees record it in their `commondir` file, otherwise it is the Git
+/// directory itself.
+async fn common_dir(git_dir: &Path) -> PathBuf {
+    read_to_string(git_dir.join("commondir")).await.map_or_else(
+        |_| git_dir.to_path_buf(),
+        |common_dir| normalize(&git_dir.join(common_dir.trim())),
+    )
+}
+
+/// Find the main working tree of a linked worktree.
+///
+/// # Returns
+///
+/// The root of the main working tree, or its Git directory for bare repositories. `None`
+/// if `repo_root` is not a linked worktree.
+pub async fn main_worktree(repo_root: &Path) -> Option<PathBuf> {
+    let git_dir = git_dir(repo_root).await?;
+    if !is_file(&git_dir.join("commondir")).await {
+        return None;
+    }
+
+    let common_dir = common_dir(&git_dir).await;
+    if common_dir.file_name().is_some_and(|name| name == ".git") {
+        common_dir.parent().map(Path::to_path_buf)
+    } else {
+        Some(common_dir)
+    }
+}
+
+/// List the linked worktrees of a repository, as recorded in its `worktrees` directory.
+///
+/// Worktrees whose directory no longer exists are skipped.
+pub async fn linked_worktrees(repo_root: &Path) -> Vec<PathBuf> {
+    let Some(git_dir) = git_dir(repo_root).await else {
+        return Vec::new();
+    };
+    let Ok(mut entries) = read_dir(common_dir(&git_dir).await.join("worktrees")).await else {
+        return Vec::new();
+    };
+
+    let mut worktrees = Vec::new();
+    while let Ok(Some(entry)) = entries.next_entry().await {
+        let Some(dot_git) = read_trimmed(&entry.path().join("gitdir")).await else {
+            continue;
+        };
+        let dot_git = normalize(&entry.path().join(dot_git));
+        if let Some(worktree) = dot_git.parent()
+            && is_dir(worktree).await
+        {
+            worktrees.push(worktree.to_path_buf());
+        }
+    }
+
+    worktrees.sort();
+    worktrees
+}
+
+async fn read_trimmed(path: &Path) -> Option<String> {
+    read_to_string(path)
+        .await
+        .ok()
+        .map(|contents| contents.trim().to_string())
+}
+
+/// Resolve a fully qualified reference such as `refs/heads/main` to a commit hash,
+/// looking at loose references first and `packed-refs` second.
+///
+/// References of linked worktrees are looked up in the worktree's own Git directory and
+/// then in the one shared with the main working tree.
+pub async fn resolve_ref(git_dir: &Path, name: &str) -> Option<String> {
+    let common_dir = common_dir(git_dir).await;
+    for dir in [git_dir, &common_dir] {
+        if let Some(hash) = read_trimmed(&dir.join(name)).await
+            && !hash.is_empty()
+        {
+            return Some(hash);
+        }
+    }
+
+    packed_refs(&common_dir)
+        .await
+        .into_iter()
+        .find_map(|(hash, reference)| (reference == name).then_some(hash))
+}
+
+/// Read `packed-refs` as `(hash, reference)` pairs, skipping peeled tag entries.
+pub async fn packed_refs(git_dir: &Path) -> Vec<(String, String)> {
+    let Ok(contents) = read_to_string(git_dir.join("packed-refs")).await else {
+        return Vec::new();
+    };
+
+    contents
+        .lines()
+        .filter(|line| !line.starts_with('#') && !line.starts_with('^'))
+        .filter_map(|line| {
+            let (hash, reference) = line.split_once(' ')?;
+            Some((hash.to_string(), reference.trim().to_string()))
+        })
+        .collect()
+}
+
+/// Detect an operation left in progress in the repository.
+async fn operation(git_dir: &Path) -> Option<GitOperation> {
+    if is_dir(&git_dir.join("rebase-merge")).await {
+        return Some(GitOperation::Rebase);
+    }
+    if is_dir(&git_dir.join("rebase-apply")).await {
+        return Some(if exists(&git_dir.join("rebase-apply/applying")).await {
+            GitOperation::Am
+        } else {
+            GitOperation::Rebase
+        });
+    }
+
+    for (file, operation) in [
+        ("MERGE_HEAD", GitOperation::Merge),
+        ("CHERRY_PICK_HEAD", GitOperation::CherryPick),
+        ("REVERT_HEAD", GitOperation::Revert),
+        ("BISECT_LOG", GitOperation::Bisect),
+    ] {
+        if exists(&git_dir.join(file)).await {
+            return Some(operation);
+        }
+    }
+    None
+}
+
+/// Read the branch being rebased, which Git records while `HEAD` is detached.
+async fn rebase_branch(git_dir: &Path) -> Option<String> {
+    for dir in ["rebase-merge", "rebase-apply"] {
+        if let Some(head_name) = read_trimmed(&git_dir.join(dir).join("head-name")).await {
+            return head_name.strip_prefix("refs/heads/").map(str::to_string);
+        }
+    }
+    None
+}
+
+fn short_hash(hash: &str) -> String {
+    hash.chars().take(SHORT_HASH_LEN).collect()
+}
+
+/// Read the branch, `HEAD` commit and in-progress operations of a repository without
+/// spawning `git`.
+///
+/// # Returns
+///
+/// The repository state, or `None` if `repo_root` has no readable `HEAD`.
+pub async fn read_git_info(repo_root: &Path) -> Option<GitInfo> {
+    let git_dir = git_dir(repo_root).await?;
+    let head = read_trimmed(&git_dir.join("HEAD")).await?;
+
+    let mut info = GitInfo {
+        operation: operation(&git_dir).await,
+        ..GitInfo::default()
+    };
+
+    if let Some(reference) = head.strip_prefix("ref:") {
+        let reference = reference.trim();
+        info.branch = Some(
+            reference
+                .strip_prefix("refs/heads/")
+                .unwrap_or(reference)
+                .to_string(),
+        );
+        info.head = resolve_ref(&git_dir, reference)
+            .await
+            .map(|hash| short_hash(&hash));
+    } else {
+        info.detached = true;
+        info.head = Some(short_hash(&head));
+        info.branch = rebase_branch(&git_dir).await;
+    }
+
+    Some(info)
+}
+
+/// Working tree status of a repository.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct GitStatus {
+    /// Whether there are any staged, modified, untracked or conflicted files.
+    pub dirty: bool,
+    pub staged: usize,
+    pub modified: usize,
+    pub untracked: usize,
+    pub conflicted: usize,
+    pub stashes: usize,
+    /// Upstream tracking branch of the current branch, if any.
+    pub upstream: Option<String>,
+    /// Commits on the current branch that are not on its upstream.
+    pub ahead: Option<usize>,
+    /// Commits on the upstream that are not on the current branch.
+    pub behind: Option<usize>,
+}
+
+/// Parse the output of `git status --porcelain=v2 --branch -z`.
+pub fn parse_status(output: &[u8]) -> GitStatus {
+    let mut status = GitStatus::default();
+    let mut entries = output.split(|&byte| byte == 0);
+
+    while let Some(entry) = entries.next() {
+        let entry = String::from_utf8_lossy(entry);
+        let mut fields = entry.split(' ');
+
+        match fields.next() {
+            Some("#") => match fields.next() {
+                Some("branch.upstream") => status.upstream = fields.next().map(str::to_string),
+                Some("branch.ab") => {
+                    let mut count = |prefix| {
+                        fields
+                            .next()
+                            .and_then(|field| field.strip_prefix(prefix))
+                            .and_then(|count| count.parse().ok())
+                    };
+                    status.ahead = count('+');
+                    status.behind = count('-');
+                }
+                _ => {}
+            },
+            Some(kind @ ("1" | "2")) => {
+                let mut xy = fields.next().unwrap_or("..").chars();
+                if xy.next().is_some_and(|x| x != '.') {
+                    status.staged += 1;
+                }
+                if xy.next().is_some_and(|y| y != '.') {
+                    status.modified += 1;
+                }
+                // Renames and copies are followed by the original path.
+                if kind == "2" {
+                    entries.next();
+                }
+            }
+            Some("u") => status.conflicted += 1,
+            Some("?") => status.untracked += 1,
+            _ => {}
+        }
+    }
+
+    status.dirty = status.staged + status.modified + status.untracked + status.conflicted > 0;
+    status
+}
+
+/// Count the stash entries of a repository from the reflog of `refs/stash`.
+pub async fn stash_count(repo_root: &Path) -> usize {
+    let Some(git_dir) = git_dir(repo_root).await else {
+        return 0;
+    };
+
+    read_to_string(common_dir(&git_dir).await.join("logs/refs/stash"))
+        .await
+        .map(|log| log.lines().filter(|line| !line.is_empty()).count())
+        .unwrap_or_default()
+}
+
+impl Display for GitStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(if self.dirty { "dirty" } else { "clean" })?;
+
+        for (label, count) in [
+            ("staged", self.staged),
+            ("modified", self.modified),
+            ("untracked", self.untracked),
+            ("conflicted", self.conflicted),
+            ("stashes", self.stashes),
+        ] {
+            if count > 0 {
+                write!(f, " {label}:{count}")?;
+            }
+        }
+
+        if let (Some(ahead), Some(behind)) = (self.ahead, self.behind) {
+            write!(f, " ahead:{ahead} behind:{behind}")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// A remote configured in a repository.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Remote {
+    pub name: String,
+    /// First `url` configured for the remote.
+    pub url: Option<String>,
+    /// The URL in the form returned by [`normalize_url`].
+    pub normalized: Option<String>,
+}
+
+/// Normalize a remote URL so the different forms of the same repository compare equal.
+///
+/// The scheme, user name, port and a trailing `.git` are dropped and the host is
+/// lowercased, so `git@github.com:acme/app.git`, `ssh://git@github.com:22/acme/app` and
+/// `https://github.com/acme/app.git` all become `github.com/acme/app`. Local paths and
+/// `file://` URLs only lose the trailing `.git`.
+pub fn normalize_url(url: &str) -> String {
+    let url = url.trim();
+    let (authority, path) = match url.split_once("://") {
+        Some((scheme, rest)) if scheme.eq_ignore_ascii_case("file") => {
+            return strip_git_suffix(rest).to_string();
+        }
+        Some((_, rest)) => rest.split_once('/').unwrap_or((rest, "")),
+        // scp-like `user@host:path`, as long as the part before the colon is not a path
+        // or a Windows drive letter.
+        None => match url.split_once(':') {
+            Some((authority, path)) if authority.len() > 1 && !authority.contains(['/', '\\']) => {
+                (authority, path)
+            }
+            _ => return strip_git_suffix(url).to_string(),
+        },
+    };
+
+    let host = authority.rsplit('@').next().unwrap_or(authority);
+    let host = host.split(':').next().unwrap_or(host).to_ascii_lowercase();
+    let path = strip_git_suffix(path.trim_start_matches('/'));
+
+    if path.is_empty() {
+        host
+    } else {
+        format!("{host}/{path}")
+    }
+}
+
+fn strip_git_suffix(path: &str) -> &str {
+    let path = path.trim_end_matches('/');
+    path.strip_suffix(".git").unwrap_or(path)
+}
+
+/// A `key = value` entry of a file in Git's configuration format.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct ConfigEntry {
+    /// Section name, lowercased.
+    section: String,
+    /// Subsection name, such as the remote name in `[remote "origin"]`.
+    subsection: Option<String>,
+    /// Key name, lowercased.
+    key: String,
+    value: String,
+}
+
+/// Parse a file in Git's configuration format, such as `.git/config` or `.gitmodules`.
+///
+/// Section and key names are case-insensitive and returned lowercased. Includes are not
+/// followed.
+fn parse_config(contents: &str) -> Vec<ConfigEntry> {
+    let mut entries = Vec::new();
+    let mut section = String::new();
+    let mut subsection = None;
+
+    for line in contents.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with(['#', ';']) {
+            continue;
+        }
+
+        if let Some(header) = line.strip_prefix('[') {
+            let header = header.split(']').next().unwrap_or_default().trim();
+            let (name, sub) = match header.split_once(' ') {
+                Some((name, sub)) => (name, Some(sub.trim().trim_matches('"'))),
+                // Legacy `[section.subsection]` headers, whose subsection is
+                // case-insensitive like the section.
+                None => match header.split_once('.') {
+                    Some((name, sub)) => {
+                        section = name.to_ascii_lowercase();
+                        subsection = Some(sub.to_ascii_lowercase());
+                        continue;
+                    }
+                    None => (header, None),
+                },
+            };
+            section = name.to_ascii_lowercase();
+            subsection = sub.map(str::to_string);
+            continue;
+        }
+
+        // A key without a value, possibly followed by a comment, is a boolean.
+        let (key, value) = match line.find(['=', '#', ';']) {
+            Some(end) if line[end..].starts_with('=') => {
+                (&line[..end], parse_value(&line[end + 1..]))
+            }
+            Some(end) => (&line[..end], "true".to_string()),
+            None => (line, "true".to_string()),
+        };
+        entries.push(ConfigEntry {
+            section: section.clone(),
+            subsection: subsection.clone(),
+            key: key.trim().to_ascii_lowercase(),
+            value,
+        });
+    }
+
+    entries
+}
+
+/// Parse a configuration value the way Git does: double quotes are removed, backslash
+/// escapes are resolved, and a `#` or `;` outside of quotes starts a comment.
+///
+/// Whitespace outside of quotes is dropped at either end of the value and kept within.
+fn parse_value(raw: &str) -> String {
+    let mut value = String::new();
+    let mut quoted = false;
+    // Whitespace seen outside of quotes, added once more of the value follows.
+    let mut spaces = 0;
+    let mut chars = raw.chars();
+
+    while let Some(c) = chars.next() {
+        if !quoted {
+            if c.is_whitespace() {
+                if !value.is_empty() {
+                    spaces += 1;
+                }
+                continue;
+            }
+            if c == '#' || c == ';' {
+                break;
+            }
+        }
+        value.extend(std::iter::repeat_n(' ', spaces));
+        spaces = 0;
+
+        match c {
+            '"' => quoted = !quoted,
+            '\\' => match chars.next() {
+                Some('n') => value.push('\n'),
+                Some('t') => value.push('\t'),
+                Some('b') => value.push('\u{8}'),
+                Some(escaped) => value.push(escaped),
+                None => {}
+            },
+            _ => value.push(c),
+        }
+    }
+
+    value
+}
+
+/// Read the remotes configured in the repository's `config` file without spawning
+/// `git`.
+///
+/// Only the repository's own configuration is considered, includes and global
+/// configuration are ignored.
+pub async fn read_remotes(repo_root: &Path) -> Vec<Remote> {
+    let Some(git_dir) = git_dir(repo_root).await else {
+        return Vec::new();
+    };
+    let Ok(config) = read_to_string(common_dir(&git_dir).await.join("config")).await else {
+        return Vec::new();
+    };
+
+    let mut remotes = Vec::<Remote>::new();
+    for entry in parse_config(&config) {
+        let (true, Some(name)) = (entry.section == "remote", entry.subsection) else {
+            continue;
+        };
+
+        let index = remotes
+            .iter()
+            .position(|remote| remote.name == name)
+            .unwrap_or_else(|| {
+                remotes.push(Remote {
+                    name,
+                    url: None,
+                    normalized: None,
+                });
+                remotes.len() - 1
+            });
+        if entry.key == "url" && remotes[index].url.is_none() {
+            remotes[index].normalized = Some(normalize_url(&entry.value));
+            remotes[index].url = Some(entry.value);
+        }
+    }
+
+    remotes
+}
+
+/// Read the paths of the submodules declared in a repository's `.gitmodules`.
+///
+/// # Returns
+///
+/// The submodule directories joined onto `repo_root`, in declaration order.
+pub async fn read_submodules(repo_root: &Path) -> Vec<PathBuf> {
+    let Ok(gitmodules) = read_to_string(repo_root.join(".gitmodules")).await else {
+        return Vec::new();
+    };
+
+    parse_config(&gitmodules)
+        .into_iter()
+        .filter(|entry| entry.section == "submodule" && entry.key == "path")
+        .map(|entry| repo_root.join(normalize(Path::new(&entry.value))))
+        .collect()
+}
+
+/// Find the repository that declares `repo_root` as one of its submodules.
+///
+/// # Returns
+///
+/// The root of the closest enclosing repository, if it lists `repo_root` in its
+/// `.gitmodules`.
+pub async fn superproject(repo_root: &Path) -> Option<PathBuf> {
+    let mut parent = None;
+    for dir in repo_root
+        .ancestors()
+        .skip(1)
+        .take_while(|dir| !dir.as_os_str().is_empty())
+    {
+        if is_repo_root(dir).await {
+            parent = Some(dir);
+            break;
+        }
+    }
+    let parent = parent?;
+
+    read_submodules(parent)
+        .await
+        .iter()
+        .any(|submodule| submodule == repo_root)
+        .then(|| parent.to_path_buf())
+}
+
+/// The commit `HEAD` points to.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct LastCommit {
+    /// Committer date as a Unix timestamp.
+    pub timestamp: i64,
+    /// Committer date in ISO 8601 format.
+    pub date: String,
+}
+
+/// A local branch with commits that are not on any remote-tracking branch.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct UnpushedBranch {
+    pub branch: String,
+    /// Number of commits only reachable from the local branch.
+    pub commits: usize,
+}
+
+/// Local work of a repository that only exists on this machine.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct Unpushed {
+    /// Whether the repository has no remote configured at all.
+    pub no_remote: bool,
+    /// Local branches with commits missing from every remote-tracking branch.
+    pub branches: Vec<UnpushedBranch>,
+}
+
+impl Unpushed {
+    /// Whether everything in the repository is available on a remote.
+    pub const fn is_empty(&self) -> bool {
+        !self.no_remote && self.branches.is_empty()
+    }
+}
+
+impl Display for Unpushed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let remote = self.no_remote.then_some("no-remote".to_string());
+        let branches = self
+            .branches
+            .iter()
+            .map(|branch| format!("{}:{}", branch.branch, branch.commits));
+
+        let parts = remote.into_iter().chain(branches).collect::<Vec<_>>();
+        f.write_str(&parts.join(" "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(section: &str, subsection: Option<&str>, key: &str, value: &str) -> ConfigEntry {
+        ConfigEntry {
+            section: section.to_string(),
+            subsection: subsection.map(str::to_string),
+            key: key.to_string(),
+            value: value.to_string(),
+        }
+    }
+
+    #[test]
+    fn comments_end_values_outside_of_quotes() {
+        let entries = parse_config(
+            r#"
+            # A comment line
+            ; Another one
+            [remote "origin"]
+                url = git@github.com:acme/app.git ; the main remote
+                pushurl = "ssh://host/a;b#c" # quoted
+                fetch = +refs/heads/*:refs/remotes/origin/*#no space
+            "#,
+        );
+
+        assert_eq!(
+            entries,
+            [
+                entry(
+                    "remote",
+                    Some("origin"),
+                    "url",
+                    "git@github.com:acme/app.git"
+                ),
+                entry("remote", Some("origin"), "pushurl", "ssh://host/a;b#c"),
+                entry(
+                    "remote",
+                    Some("origin"),
+                    "fetch",
+                    "+refs/heads/*:refs/remotes/origin/*"
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn quotes_escapes_and_whitespace() {
+        assert_eq!(parse_value(r#" "say \"hi\"" "#), r#"say "hi""#);
+        assert_eq!(parse_value(r"a\\b\tc\nd"), "a\\b\tc\nd");
+        assert_eq!(parse_value("  two  words  "), "two  words");
+        assert_eq!(parse_value(r#""  padded  ""#), "  padded  ");
+        assert_eq!(parse_value(r#"half" quoted ; "rest"#), "half quoted ; rest");
+    }
+
+    #[test]
+    fn keys_without_values_are_true() {
+        let entries = parse_config("[core]\n\tbare\n\tFileMode # comment\n\tempty =\n");
+
+        assert_eq!(
+            entries,
+            [
+                entry("core", None, "bare", "true"),
+                entry("core", None, "filemode", "true"),
+                entry("core", None, "empty", ""),
+            ]
+        );
+    }
+
+    #[test]
+    fn legacy_subsection_headers() {
+        let entries = parse_config(
+            "[Remote.Origin]\n\turl = /srv/app.git\n[submodule \"Lib\"]\n\tPath = lib\n",
+        );
+
+        assert_eq!(
+            entries,
+            [
+                entry("remote", Some("origin"), "url", "/srv/app.git"),
+                entry("submodule", Some("Lib"), "path", "lib"),
+            ]
+        );
+    }
+
+    #[test]
+    fn remote_forms_normalize_to_the_same_value() {
+        for url in [
+            "git@github.com:acme/app.git",
+            "ssh://git@github.com/acme/app.git",
+            "ssh://git@github.com:22/acme/app",
+            "https://github.com/acme/app.git",
+            "https://user@GitHub.com:443/acme/app/",
+            "git://github.com/acme/app",
+        ] {
+            assert_eq!(normalize_url(url), "github.com/acme/app", "{url}");
+        }
+    }
+
+    #[test]
+    fn local_remotes_keep_their_path() {
+        assert_eq!(normalize_url("file:///srv/git/app.git"), "/srv/git/app");
+        assert_eq!(normalize_url("/srv/git/app.git/"), "/srv/git/app");
+        assert_eq!(normalize_url("../app.git"), "../app");
+        assert_eq!(normalize_url(r"C:\repos\app.git"), r"C:\repos\app");
+    }
+}
@@ -0,0 +1,416 @@
+use crate::{
+    dirs::cache_dir,
+    errors::{ProjectFinderError, Result},
+    project::Project,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    fs,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+/// Version of the on-disk format, an index of another version is rebuilt.
+const INDEX_VERSION: u32 = 3;
+
+/// Entries found by scanning directories with `fd`, before they are resolved to
+/// projects.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Listing {
+    /// Roots of working copies, found by their `.git` or similar entry.
+    pub repos: BTreeSet<PathBuf>,
+    /// Bare Git repositories, found by their `HEAD` file.
+    pub bare_repos: BTreeSet<PathBuf>,
+    /// Marker files.
+    pub markers: BTreeSet<PathBuf>,
+}
+
+impl Listing {
+    pub fn extend(&mut self, other: Self) {
+        self.repos.extend(other.repos);
+        self.bare_repos.extend(other.bare_repos);
+        self.markers.extend(other.markers);
+    }
+
+    /// Forget the entries found directly in a directory, which are the directory itself
+    /// for repositories and the files in it for markers.
+    pub fn remove_in(&mut self, dir: &Path) {
+        self.repos.remove(dir);
+        self.bare_repos.remove(dir);
+        self.markers.retain(|marker| marker.parent() != Some(dir));
+    }
+
+    /// Forget the entries found in a directory and below it.
+    pub fn remove_below(&mut self, dir: &Path) {
+        self.repos.retain(|repo| !repo.starts_with(dir));
+        self.bare_repos.retain(|repo| !repo.starts_with(dir));
+        self.markers.retain(|marker| !marker.starts_with(dir));
+    }
+}
+
+/// The part of the index that is the same in every version.
+#[derive(Deserialize)]
+struct Version {
+    version: u32,
+}
+
+/// Projects discovered in a set of search paths, stored in `index.json` in the cache
+/// directory so later searches can skip the scan.
+///
+/// All paths are absolute.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Index {
+    pub version: u32,
+    /// Time the index was built, in seconds since the Unix epoch.
+    pub built_at: u64,
+    /// Search paths that were scanned.
+    pub paths: Vec<PathBuf>,
+    /// Maximum search depth of the scan.
+    pub depth: usize,
+    /// Modification times of the directories seen by the scan.
+    pub dirs: BTreeMap<PathBuf, SystemTime>,
+    /// What the scan found, kept so a rescan only has to list the changed directories.
+    pub listing: Listing,
+    pub projects: Vec<Project>,
+}
+
+impl Index {
+    /// Location of the index file.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the cache directory cannot be determined.
+    pub fn path() -> Result<PathBuf> {
+        Ok(cache_dir()?.join("index.json"))
+    }
+
+    /// Read the index, if it was built by this version of project-finder.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the index exists but cannot be read or parsed.
+    pub fn load() -> Result<Option<Self>> {
+        let path = Self::path()?;
+        let contents = match fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+
+        let parse_error = |e: serde_json::Error| {
+            ProjectFinderError::IndexParseFailed(path.clone(), e.to_string())
+        };
+        // Only the version is checked before parsing the rest, as older formats may not
+        // parse at all.
+        let version = serde_json::from_str::<Version>(&contents).map_err(parse_error)?;
+        if version.version != INDEX_VERSION {
+            return Ok(None);
+        }
+
+        serde_json::from_str(&contents)
+            .map(Some)
+            .map_err(parse_error)
+    }
+
+    /// Write the index, replacing the previous one atomically.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the cache directory cannot be created or written to.
+    pub fn save(&self) -> Result<()> {
+        let path = Self::path()?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+
+        let contents = serde_json::to_string(self)
+            .map_err(|e| ProjectFinderError::IndexParseFailed(path.clone(), e.to_string()))?;
+        let temporary = path.with_extension(format!("json.{}", std::process::id()));
+        fs::write(&temporary, contents)?;
+        fs::rename(&temporary, &path)?;
+        Ok(())
+    }
+
+    /// Remove the index.
+    ///
+    /// # Returns
+    ///
+    /// Whether there was an index to remove.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the index cannot be removed.
+    pub fn clear() -> Result<bool> {
+        let removed = remove_file(&Self::path()?)?;
+        remove_file(&Caches::path()?)?;
+        Ok(removed)
+    }
+
+    /// Create an index of the projects found by a scan of `paths`.
+    pub const fn new(
+        paths: Vec<PathBuf>,
+        depth: usize,
+        dirs: BTreeMap<PathBuf, SystemTime>,
+        listing: Listing,
+        projects: Vec<Project>,
+        built_at: u64,
+    ) -> Self {
+        Self {
+            version: INDEX_VERSION,
+            built_at,
+            paths,
+            depth,
+            dirs,
+            listing,
+            projects,
+        }
+    }
+
+    /// Check whether a search of the absolute `paths` up to `depth` only finds what the
+    /// scan of the index found, as every path was scanned and the search does not go
+    /// deeper than the scan.
+    ///
+    /// Directories below a scanned path are not covered, since a scan starting there
+    /// does not see the workspaces enclosing it.
+    pub fn covers(&self, paths: &[PathBuf], depth: usize) -> bool {
+        depth <= self.depth && paths.iter().all(|path| self.paths.contains(path))
+    }
+
+    /// The indexed paths followed by those of the absolute `paths` that are not indexed
+    /// yet.
+    pub fn merged_paths(&self, paths: &[PathBuf]) -> Vec<PathBuf> {
+        let mut merged = self.paths.clone();
+        for path in paths {
+            if !merged.contains(path) {
+                merged.push(path.clone());
+            }
+        }
+        merged
+    }
+
+    /// Directories whose modification time changed since the scan, or that are gone.
+    pub fn changed_dirs(&self) -> Vec<&Path> {
+        self.dirs
+            .iter()
+            .filter(|(dir, mtime)| {
+                fs::metadata(dir)
+                    .and_then(|metadata| metadata.modified())
+                    .map_or(true, |modified| modified != **mtime)
+            })
+            .map(|(dir, _)| dir.as_path())
+            .collect()
+    }
+}
+
+/// Project root resolved for a directory containing a marker file.
+#[derive(Debug, Serialize, Deserialize)]
+struct RootEntry {
+    dir: PathBuf,
+    marker: String,
+    root: PathBuf,
+}
+
+/// The workspace and project roots resolved by the latest scan for the index, stored in
+/// `caches.json` in the cache directory.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Caches {
+    workspaces: BTreeMap<PathBuf, bool>,
+    roots: Vec<RootEntry>,
+}
+
+impl Caches {
+    fn path() -> Result<PathBuf> {
+        Ok(cache_dir()?.join("caches.json"))
+    }
+
+    pub fn new(
+        workspaces: &HashMap<PathBuf, bool>,
+        roots: &HashMap<(PathBuf, String), PathBuf>,
+    ) -> Self {
+        Self {
+            workspaces: workspaces
+                .iter()
+                .map(|(dir, &is_workspace)| (dir.clone(), is_workspace))
+                .collect(),
+            roots: roots
+                .iter()
+                .map(|((dir, marker), root)| RootEntry {
+                    dir: dir.clone(),
+                    marker: marker.clone(),
+                    root: root.clone(),
+                })
+                .collect(),
+        }
+    }
+
+    /// Read the caches, which are empty if they were never written.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the caches exist but cannot be read or parsed.
+    pub fn load() -> Result<Self> {
+        let path = Self::path()?;
+        match fs::read_to_string(&path) {
+            Ok(contents) => serde_json::from_str(&contents)
+                .map_err(|e| ProjectFinderError::IndexParseFailed(path, e.to_string())),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Write the caches, replacing the previous ones atomically.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the cache directory cannot be written to.
+    pub fn save(&self) -> Result<()> {
+        let path = Self::path()?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+
+        let contents = serde_json::to_string(self)
+            .map_err(|e| ProjectFinderError::IndexParseFailed(path.clone(), e.to_string()))?;
+        let temporary = path.with_extension(format!("json.{}", std::process::id()));
+        fs::write(&temporary, contents)?;
+        fs::rename(&temporary, &path)?;
+        Ok(())
+    }
+
+    /// The cached workspace and project roots, in the form the finder uses.
+    pub fn into_maps(self) -> (HashMap<PathBuf, bool>, HashMap<(PathBuf, String), PathBuf>) {
+        let roots = self
+            .roots
+            .into_iter()
+            .map(|entry| ((entry.dir, entry.marker), entry.root))
+            .collect();
+        (self.workspaces.into_iter().collect(), roots)
+    }
+}
+
+/// Remove a file, returning whether it existed.
+fn remove_file(path: &Path) -> Result<bool> {
+    match fs::remove_file(path) {
+        Ok(()) => Ok(true),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(false),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{io, time::Duration};
+
+    fn paths(paths: &[&str]) -> Vec<PathBuf> {
+        paths.iter().map(PathBuf::from).collect()
+    }
+
+    fn listing() -> Listing {
+        Listing {
+            repos: paths(&["/code/app", "/code/app/vendor/lib"])
+                .into_iter()
+                .collect(),
+            bare_repos: paths(&["/code/mirror.git"]).into_iter().collect(),
+            markers: paths(&[
+                "/code/app/Cargo.toml",
+                "/code/app/web/package.json",
+                "/code/tool/go.mod",
+            ])
+            .into_iter()
+            .collect(),
+        }
+    }
+
+    fn index(dirs: BTreeMap<PathBuf, SystemTime>) -> Index {
+        Index::new(
+            paths(&["/code", "/work"]),
+            3,
+            dirs,
+            Listing::default(),
+            Vec::new(),
+            0,
+        )
+    }
+
+    #[test]
+    fn remove_in_keeps_entries_below() {
+        let mut listing = listing();
+        listing.remove_in(Path::new("/code/app"));
+
+        assert_eq!(
+            listing.repos,
+            paths(&["/code/app/vendor/lib"]).into_iter().collect()
+        );
+        assert_eq!(listing.bare_repos.len(), 1);
+        assert_eq!(
+            listing.markers,
+            paths(&["/code/app/web/package.json", "/code/tool/go.mod"])
+                .into_iter()
+                .collect()
+        );
+    }
+
+    #[test]
+    fn remove_below_drops_whole_subtree() {
+        let mut listing = listing();
+        listing.remove_below(Path::new("/code/app"));
+
+        assert!(listing.repos.is_empty());
+        assert_eq!(listing.bare_repos.len(), 1);
+        assert_eq!(
+            listing.markers,
+            paths(&["/code/tool/go.mod"]).into_iter().collect()
+        );
+    }
+
+    #[test]
+    fn covers_indexed_paths_up_to_depth() {
+        let index = index(BTreeMap::new());
+
+        assert!(index.covers(&paths(&["/work", "/code"]), 3));
+        assert!(index.covers(&paths(&["/code"]), 2));
+        assert!(!index.covers(&paths(&["/code"]), 4));
+        assert!(!index.covers(&paths(&["/code", "/other"]), 3));
+        // A scan of a subdirectory does not see the workspaces enclosing it.
+        assert!(!index.covers(&paths(&["/code/app"]), 3));
+    }
+
+    #[test]
+    fn merged_paths_appends_new_paths() {
+        let index = index(BTreeMap::new());
+
+        assert_eq!(
+            index.merged_paths(&paths(&["/other", "/code"])),
+            paths(&["/code", "/work", "/other"])
+        );
+    }
+
+    #[test]
+    fn changed_dirs_compares_modification_times() -> io::Result<()> {
+        let root = tempfile::tempdir()?;
+        let unchanged = root.path().join("unchanged");
+        let changed = root.path().join("changed");
+        let removed = root.path().join("removed");
+        fs::create_dir(&unchanged)?;
+        fs::create_dir(&changed)?;
+
+        let modified = |dir: &Path| fs::metadata(dir)?.modified();
+        let dirs = BTreeMap::from([
+            (unchanged.clone(), modified(&unchanged)?),
+            (
+                changed.clone(),
+                modified(&changed)? - Duration::from_mins(1),
+            ),
+            (removed.clone(), SystemTime::UNIX_EPOCH),
+        ]);
+
+        assert_eq!(
+            index(dirs).changed_dirs(),
+            [changed.as_path(), removed.as_path()]
+        );
+        Ok(())
+    }
+}
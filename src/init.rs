@@ -0,0 +1,212 @@
+use clap::ValueEnum;
+use std::path::Path;
+
+/// A shell `init` can generate functions for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+    Nushell,
+}
+
+const BASH: &str = r#"# Shell integration for project-finder, load it with:
+#   eval "$(project-finder init bash)"
+
+__project_finder_paths=(__PATHS__)
+
+# Record every directory change as a visit, so projects can be ranked by frecency, and
+# refresh the project index in the background so new projects show up.
+__project_finder_visit() {
+    if [ "${__project_finder_pwd:-}" != "$PWD" ]; then
+        __project_finder_pwd="$PWD"
+        command project-finder visit -- "$PWD" 2>/dev/null
+        command project-finder index refresh --detach 2>/dev/null
+    fi
+}
+
+if [[ ";${PROMPT_COMMAND:-};" != *";__project_finder_visit;"* ]]; then
+    PROMPT_COMMAND="__project_finder_visit;${PROMPT_COMMAND:-}"
+fi
+
+# Change into a project: picked interactively without arguments, the best match of the
+# query otherwise.
+__CMD__() {
+    local dir
+    if [ "$#" -eq 0 ]; then
+        dir="$(command project-finder --pick --cached "${__project_finder_paths[@]}")" || return
+    else
+        dir="$(command project-finder find --cached --color never -n 1 --sort frecency -- "$*" "${__project_finder_paths[@]}")" || return
+    fi
+    cd -- "$dir" || return
+}
+
+__project_finder_complete() {
+    local IFS=$'\n'
+    local names
+    names="$(command project-finder --cached --format '{name}' "${__project_finder_paths[@]}" 2>/dev/null)"
+    # shellcheck disable=SC2207
+    COMPREPLY=($(compgen -W "$names" -- "${COMP_WORDS[COMP_CWORD]}"))
+}
+
+complete -F __project_finder_complete __CMD__
+"#;
+
+const ZSH: &str = r#"# Shell integration for project-finder, load it with:
+#   eval "$(project-finder init zsh)"
+
+typeset -ga __project_finder_paths
+__project_finder_paths=(__PATHS__)
+
+# Record every directory change as a visit, so projects can be ranked by frecency, and
+# refresh the project index in the background so new projects show up.
+__project_finder_visit() {
+    command project-finder visit -- "$PWD" 2>/dev/null
+    command project-finder index refresh --detach 2>/dev/null
+}
+
+typeset -ga chpwd_functions
+if (( ! ${chpwd_functions[(I)__project_finder_visit]} )); then
+    chpwd_functions+=(__project_finder_visit)
+fi
+
+# Change into a project: picked interactively without arguments, the best match of the
+# query otherwise.
+__CMD__() {
+    local dir
+    if (( $# == 0 )); then
+        dir="$(command project-finder --pick --cached "${__project_finder_paths[@]}")" || return
+    else
+        dir="$(command project-finder find --cached --color never -n 1 --sort frecency -- "$*" "${__project_finder_paths[@]}")" || return
+    fi
+    cd -- "$dir"
+}
+
+__project_finder_complete() {
+    local -a names
+    names=("${(@f)$(command project-finder --cached --format '{name}' "${__project_finder_paths[@]}" 2>/dev/null)}")
+    compadd -a names
+}
+
+if (( $+functions[compdef] )); then
+    compdef __project_finder_complete __CMD__
+fi
+"#;
+
+const FISH: &str = r#"# Shell integration for project-finder, load it with:
+#   project-finder init fish | source
+
+set -g __project_finder_paths __PATHS__
+
+# Record every directory change as a visit, so projects can be ranked by frecency, and
+# refresh the project index in the background so new projects show up.
+function __project_finder_visit --on-variable PWD
+    command project-finder visit -- "$PWD" 2>/dev/null
+    command project-finder index refresh --detach 2>/dev/null
+end
+
+# Change into a project: picked interactively without arguments, the best match of the
+# query otherwise.
+function __CMD__
+    set -l dir
+    if test (count $argv) -eq 0
+        set dir (command project-finder --pick --cached $__project_finder_paths); or return
+    else
+        set dir (command project-finder find --cached --color never -n 1 --sort frecency -- "$argv" $__project_finder_paths); or return
+    end
+    cd $dir
+end
+
+complete -c __CMD__ -f -a '(command project-finder --cached --format "{name}" $__project_finder_paths 2>/dev/null)'
+"#;
+
+const NUSHELL: &str = r"# Shell integration for project-finder, load it with:
+#   project-finder init nushell | save -f ~/.project-finder.nu
+#   source ~/.project-finder.nu
+
+$env.__PROJECT_FINDER_PATHS = [__PATHS__]
+
+# Record every directory change as a visit, so projects can be ranked by frecency, and
+# refresh the project index in the background so new projects show up.
+export-env {
+    $env.config = (
+        $env.config?
+        | default {}
+        | upsert hooks { default {} }
+        | upsert hooks.env_change { default {} }
+        | upsert hooks.env_change.PWD { default [] }
+    )
+    let hooked = (
+        $env.config.hooks.env_change.PWD
+        | any { try { get __project_finder_hook } catch { false } }
+    )
+    if not $hooked {
+        $env.config.hooks.env_change.PWD = ($env.config.hooks.env_change.PWD | append {
+            __project_finder_hook: true,
+            code: {|_, dir|
+                ^project-finder visit -- $dir
+                ^project-finder index refresh --detach
+            }
+        })
+    }
+}
+
+def __project_finder_names [] {
+    ^project-finder --cached --format '{name}' ...$env.__PROJECT_FINDER_PATHS | lines
+}
+
+# Change into a project: picked interactively without arguments, the best match of the
+# query otherwise.
+def --env __CMD__ [...query: string@__project_finder_names] {
+    let dir = if ($query | is-empty) {
+        ^project-finder --pick --cached ...$env.__PROJECT_FINDER_PATHS
+    } else {
+        ^project-finder find --cached --color never -n 1 --sort frecency -- ($query | str join ' ') ...$env.__PROJECT_FINDER_PATHS
+    }
+    cd ($dir | str trim)
+}
+";
+
+/// Check whether a name can be used for the generated function in every shell.
+pub fn is_valid_command_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
+
+/// Generate the shell functions: a hook recording visits and refreshing the project
+/// index, a `cmd` function changing into a picked or queried project among those in
+/// `paths`, and completion of project names for it.
+pub fn init_script(shell: Shell, cmd: &str, paths: &[&Path]) -> String {
+    let (template, quote): (&str, fn(&str) -> String) = match shell {
+        Shell::Bash => (BASH, quote_posix),
+        Shell::Zsh => (ZSH, quote_posix),
+        Shell::Fish => (FISH, quote_fish),
+        Shell::Nushell => (NUSHELL, quote_nushell),
+    };
+    let paths = paths
+        .iter()
+        .map(|path| quote(&path.to_string_lossy()))
+        .collect::<Vec<_>>();
+
+    template
+        .replace("__CMD__", cmd)
+        .replace("__PATHS__", &paths.join(" "))
+}
+
+/// Quote a string for bash and zsh.
+fn quote_posix(s: &str) -> String {
+    format!("'{}'", s.replace('\'', r"'\''"))
+}
+
+/// Quote a string for fish, where backslashes escape inside single quotes too.
+fn quote_fish(s: &str) -> String {
+    format!("'{}'", s.replace('\\', r"\\").replace('\'', r"\'"))
+}
+
+/// Quote a string for nushell, whose double quoted strings use JSON-like escapes.
+fn quote_nushell(s: &str) -> String {
+    serde_json::to_string(s).unwrap_or_else(|_| format!("'{s}'"))
+}
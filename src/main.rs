@@ -1,17 +1,62 @@
+mod affected;
 mod commands;
+mod completions;
 mod config;
 mod dependencies;
+mod dirs;
+mod duplicates;
 mod errors;
+mod filter;
 mod finder;
+mod frecency;
+mod fuzzy;
+mod git;
+mod index;
+mod init;
+mod manifest;
 mod marker;
+mod output;
+mod picker;
+mod project;
+mod sort;
+mod template;
+mod tree;
+mod unpushed;
+mod vcs;
 
-use crate::{config::Config, dependencies::Dependencies, finder::ProjectFinder};
+use crate::{
+    affected::find_affected,
+    completions::{print_completions, print_man},
+    config::{
+        Command, Config, FindArgs, IndexAction, IndexArgs, InitArgs, JumpArgs, RebuildArgs,
+        RefreshArgs, VisitArgs,
+    },
+    dependencies::Dependencies,
+    duplicates::find_duplicates,
+    finder::ProjectFinder,
+    frecency::{Database, now},
+    fuzzy::find_matches,
+    index::Index,
+    init::{init_script, is_valid_command_name},
+    output::{print_duplicates, print_matches, print_path, print_projects},
+    picker::pick,
+    tree::print_tree,
+    unpushed::find_unpushed,
+};
 use anyhow::{Result, anyhow};
 use clap::Parser;
-use std::process::exit;
+use std::{
+    env::{current_exe, home_dir},
+    io,
+    path::{PathBuf, absolute},
+    process::{self, Stdio, exit},
+};
 use tracing::Level;
 use tracing_subscriber::FmtSubscriber;
 
+/// Number of matches printed by `find` unless `--max-results` is given.
+const DEFAULT_FIND_RESULTS: usize = 10;
+
 #[tokio::main]
 async fn main() {
     if let Err(e) = run().await {
@@ -22,7 +67,7 @@ async fn main() {
 
 async fn run() -> Result<()> {
     // Parse CLI arguments
-    let config = Config::parse();
+    let mut config = Config::parse();
 
     // Setup logging
     let log_level = if config.verbose {
@@ -35,20 +80,310 @@ async fn run() -> Result<()> {
     tracing::subscriber::set_global_default(subscriber)
         .map_err(|e| anyhow!("Failed to set up logging: {e}"))?;
 
+    if let Some(result) = run_standalone(config.command.as_ref()) {
+        return result;
+    }
+
     // Check for required dependencies
     let deps = Dependencies::check().map_err(|e| anyhow!("{e}"))?;
 
+    // Subcommands searching for projects bring their own search paths
+    match &config.command {
+        Some(Command::Affected(args)) => config.paths.clone_from(&args.search_paths),
+        Some(Command::Unpushed(args)) => config.paths.clone_from(&args.paths),
+        Some(Command::Duplicates(args)) => config.paths.clone_from(&args.paths),
+        Some(Command::Find(args)) => config.paths.clone_from(&args.paths),
+        _ => {}
+    }
+
     // Create finder and search for projects
-    let finder = ProjectFinder::new(config, deps);
+    let finder = ProjectFinder::new(config.clone(), deps.clone());
+
+    let projects = match &config.command {
+        Some(Command::Affected(args)) => {
+            let projects = find_affected(&finder, &deps, args, config.depth)
+                .await
+                .map_err(|e| anyhow!("Failed to find affected projects: {e}"))?;
+            finder
+                .select_found(projects)
+                .await
+                .map_err(|e| anyhow!("Failed to filter affected projects: {e}"))?
+        }
+        Some(Command::Unpushed(_)) => {
+            let projects = find_unpushed(&finder)
+                .await
+                .map_err(|e| anyhow!("Failed to find unpushed work: {e}"))?;
+            finder
+                .select_found(projects)
+                .await
+                .map_err(|e| anyhow!("Failed to filter repositories: {e}"))?
+        }
+        Some(Command::Duplicates(_)) => {
+            if config.tree {
+                return Err(anyhow!("--tree is not supported by duplicates"));
+            }
+
+            let mut groups = find_duplicates(&finder)
+                .await
+                .map_err(|e| anyhow!("Failed to find duplicates: {e}"))?;
+            if config.max_results > 0 {
+                groups.truncate(config.max_results);
+            }
+
+            return print_duplicates(&groups, &config)
+                .map_err(|e| anyhow!("Failed to print duplicates: {e}"));
+        }
+        Some(Command::Find(args)) => return find(&finder, &config, args).await,
+        Some(Command::Index(IndexArgs {
+            action: IndexAction::Rebuild(args),
+        })) => return rebuild_index(&finder, &config, args).await,
+        Some(Command::Index(IndexArgs {
+            action: IndexAction::Refresh(_),
+        })) => return refresh_index(&finder).await,
+        Some(
+            Command::Visit(_)
+            | Command::Jump(_)
+            | Command::Init(_)
+            | Command::Completions(_)
+            | Command::Man
+            | Command::Index(_),
+        ) => unreachable!("handled before the search"),
+        None if config.pick => {
+            let root = pick(&finder, &config)
+                .await
+                .map_err(|e| anyhow!("Failed to pick a project: {e}"))?;
+            // Like fzf, a cancelled pick exits with the status of an interrupt.
+            let Some(root) = root else { exit(130) };
+            return print_path(&root).map_err(|e| anyhow!("Failed to print project: {e}"));
+        }
+        None => finder
+            .find_projects()
+            .await
+            .map_err(|e| anyhow!("Failed to find projects: {e}"))?,
+    };
+
+    if config.tree {
+        print_tree(&projects).await
+    } else {
+        print_projects(&projects, &config)
+    }
+    .map_err(|e| anyhow!("Failed to print projects: {e}"))?;
+
+    Ok(())
+}
+
+/// Run the commands that do not search for projects, so `fd` is not needed for them.
+fn run_standalone(command: Option<&Command>) -> Option<Result<()>> {
+    Some(match command? {
+        Command::Visit(args) => visit(args),
+        Command::Jump(args) => jump(args),
+        Command::Init(args) => init(args),
+        Command::Completions(args) => {
+            print_completions(args.shell).map_err(|e| anyhow!("Failed to print completions: {e}"))
+        }
+        Command::Man => print_man().map_err(|e| anyhow!("Failed to print man page: {e}")),
+        Command::Index(args) => match args.action {
+            IndexAction::Status => index_status(),
+            IndexAction::Clear => clear_index(),
+            IndexAction::Refresh(RefreshArgs { detach: true }) => refresh_detached(),
+            IndexAction::Rebuild(_) | IndexAction::Refresh(_) => return None,
+        },
+        Command::Affected(_) | Command::Unpushed(_) | Command::Duplicates(_) | Command::Find(_) => {
+            return None;
+        }
+    })
+}
+
+/// Print the projects best matching a fuzzy query.
+async fn find(finder: &ProjectFinder, config: &Config, args: &FindArgs) -> Result<()> {
+    if config.tree {
+        return Err(anyhow!("--tree is not supported by find"));
+    }
 
-    let projects = finder
-        .find_projects()
+    let mut hits = find_matches(finder, &args.query)
         .await
         .map_err(|e| anyhow!("Failed to find projects: {e}"))?;
+    if hits.is_empty() {
+        return Err(anyhow!("No project matches '{}'", args.query));
+    }
+    let limit = match config.max_results {
+        0 => DEFAULT_FIND_RESULTS,
+        limit => limit,
+    };
+    hits.truncate(limit);
+
+    print_matches(&hits, config, args.color.enabled())
+        .map_err(|e| anyhow!("Failed to print projects: {e}"))
+}
+
+/// Scan directories and add them to the project index, along with the directories
+/// already in it, only rescanning what changed in those if the depth is the same.
+///
+/// Paths are never dropped from the index, only [`Index::clear`] removes them.
+async fn rebuild_index(finder: &ProjectFinder, config: &Config, args: &RebuildArgs) -> Result<()> {
+    // An unreadable index is about to be replaced anyway.
+    let indexed = Index::load().ok().flatten();
+    let mut paths = indexed
+        .as_ref()
+        .map(|index| index.paths.clone())
+        .unwrap_or_default();
+    if paths.is_empty() && args.paths.is_empty() {
+        paths.push(PathBuf::from("."));
+    }
+    paths.extend(args.paths.iter().cloned());
 
-    for project in projects {
-        println!("{}", project.display());
+    let mut absolute_paths = Vec::with_capacity(paths.len());
+    for path in paths {
+        if !path.is_dir() {
+            return Err(anyhow!("Path not found: {}", path.display()));
+        }
+        let path = absolute(&path).map_err(|e| anyhow!("Invalid path: {e}"))?;
+        if !absolute_paths.contains(&path) {
+            absolute_paths.push(path);
+        }
     }
 
+    let index = finder
+        .build_index(absolute_paths, config.depth, indexed.filter(|_| !args.full))
+        .await
+        .map_err(|e| anyhow!("Failed to build index: {e}"))?;
+
+    println!(
+        "Indexed {} projects in {} directories",
+        index.projects.len(),
+        index.dirs.len()
+    );
     Ok(())
 }
+
+/// Rescan the directories of the project index that changed since it was built, with
+/// the paths and depth it was built with.
+async fn refresh_index(finder: &ProjectFinder) -> Result<()> {
+    let Some(index) = Index::load().map_err(|e| anyhow!("{e}"))? else {
+        println!("No index to refresh");
+        return Ok(());
+    };
+    if index.changed_dirs().is_empty() {
+        println!("The index is up to date");
+        return Ok(());
+    }
+
+    let (paths, depth) = (index.paths.clone(), index.depth);
+    let index = finder
+        .build_index(paths, depth, Some(index))
+        .await
+        .map_err(|e| anyhow!("Failed to refresh index: {e}"))?;
+
+    println!(
+        "Indexed {} projects in {} directories",
+        index.projects.len(),
+        index.dirs.len()
+    );
+    Ok(())
+}
+
+/// Start `index refresh` in a background process that keeps running after this one
+/// exits, so a shell hook does not wait for it.
+fn refresh_detached() -> Result<()> {
+    let program = current_exe().map_err(|e| anyhow!("Failed to locate executable: {e}"))?;
+    process::Command::new(program)
+        .args(["index", "refresh"])
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| anyhow!("Failed to start refresh: {e}"))?;
+    Ok(())
+}
+
+/// Print what the project index contains and how much of it is outdated.
+fn index_status() -> Result<()> {
+    let path = Index::path().map_err(|e| anyhow!("{e}"))?;
+    let Some(index) = Index::load().map_err(|e| anyhow!("{e}"))? else {
+        println!("No index at {}", path.display());
+        return Ok(());
+    };
+
+    let paths = index
+        .paths
+        .iter()
+        .map(|path| path.display().to_string())
+        .collect::<Vec<_>>();
+    println!("Index:       {}", path.display());
+    println!(
+        "Built:       {}",
+        format_age(now().saturating_sub(index.built_at))
+    );
+    println!("Paths:       {}", paths.join(", "));
+    println!("Depth:       {}", index.depth);
+    println!("Projects:    {}", index.projects.len());
+    println!(
+        "Directories: {} ({} changed since)",
+        index.dirs.len(),
+        index.changed_dirs().len()
+    );
+    Ok(())
+}
+
+/// Describe how long ago something happened, in the largest whole unit.
+fn format_age(seconds: u64) -> String {
+    let (count, unit) = match seconds {
+        0..60 => return "just now".to_string(),
+        60..3600 => (seconds / 60, "minute"),
+        3600..86_400 => (seconds / 3600, "hour"),
+        _ => (seconds / 86_400, "day"),
+    };
+    let plural = if count == 1 { "" } else { "s" };
+    format!("{count} {unit}{plural} ago")
+}
+
+/// Remove the project index.
+fn clear_index() -> Result<()> {
+    if Index::clear().map_err(|e| anyhow!("Failed to remove index: {e}"))? {
+        println!("Removed the index");
+    } else {
+        println!("No index to remove");
+    }
+    Ok(())
+}
+
+/// Print the shell integration functions.
+fn init(args: &InitArgs) -> Result<()> {
+    if !is_valid_command_name(&args.cmd) {
+        return Err(anyhow!("Invalid function name '{}'", args.cmd));
+    }
+
+    let paths = if args.paths.is_empty() {
+        vec![home_dir().ok_or_else(|| anyhow!("Could not determine the home directory"))?]
+    } else {
+        args.paths
+            .iter()
+            .map(absolute)
+            .collect::<io::Result<Vec<_>>>()
+            .map_err(|e| anyhow!("Invalid path: {e}"))?
+    };
+    let paths = paths.iter().map(PathBuf::as_path).collect::<Vec<_>>();
+
+    print!("{}", init_script(args.shell, &args.cmd, &paths));
+    Ok(())
+}
+
+/// Record a visit of a directory in the frecency database.
+fn visit(args: &VisitArgs) -> Result<()> {
+    let mut database = Database::open_for_update().map_err(|e| anyhow!("{e}"))?;
+    database
+        .visit(&args.path, now())
+        .map_err(|e| anyhow!("Failed to record visit: {e}"))?;
+    database
+        .save()
+        .map_err(|e| anyhow!("Failed to save visits: {e}"))
+}
+
+/// Print the best visited directory matching the keywords.
+fn jump(args: &JumpArgs) -> Result<()> {
+    let database = Database::open().map_err(|e| anyhow!("{e}"))?;
+    let visit = database
+        .jump(&args.keywords, now())
+        .ok_or_else(|| anyhow!("No visited directory matches '{}'", args.keywords.join(" ")))?;
+    print_path(&visit.path).map_err(|e| anyhow!("Failed to print directory: {e}"))
+}
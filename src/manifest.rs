@@ -0,0 +1,637 @@
+use crate::errors::{ProjectFinderError, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use std::path::{Path, PathBuf};
+use tokio::fs::read_to_string;
+use toml::{Table, Value as TomlValue};
+use tracing::warn;
+
+/// Manifests that can declare dependencies on other local projects.
+pub const DEPENDENCY_MANIFESTS: [&str; 2] = ["Cargo.toml", "package.json"];
+
+/// Cargo tables that list dependencies.
+const CARGO_DEPENDENCY_TABLES: [&str; 3] =
+    ["dependencies", "dev-dependencies", "build-dependencies"];
+
+/// npm fields that list dependencies.
+const NPM_DEPENDENCY_FIELDS: [&str; 4] = [
+    "dependencies",
+    "devDependencies",
+    "peerDependencies",
+    "optionalDependencies",
+];
+
+/// A dependency on another project that lives on the local filesystem.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LocalDependency {
+    /// Dependency referenced by a directory path (Cargo `path =`, npm `file:`/`link:`).
+    Path(PathBuf),
+    /// Dependency resolved by package name inside the workspace (npm `workspace:`).
+    Workspace(String),
+}
+
+async fn read_manifest(path: &Path) -> Result<String> {
+    read_to_string(path).await.map_err(|e| {
+        ProjectFinderError::CommandExecutionFailed(format!(
+            "Failed to read file {}: {e}",
+            path.display()
+        ))
+    })
+}
+
+fn parse_error<E: std::fmt::Display>(path: &Path, err: E) -> ProjectFinderError {
+    ProjectFinderError::ManifestParseFailed(path.to_path_buf(), err.to_string())
+}
+
+/// Parse a `Cargo.toml` file into a TOML table.
+pub async fn read_cargo_toml(path: &Path) -> Result<Table> {
+    let contents = read_manifest(path).await?;
+    contents.parse().map_err(|e| parse_error(path, e))
+}
+
+/// Parse a `package.json` file into a JSON value.
+pub async fn read_package_json(path: &Path) -> Result<JsonValue> {
+    let contents = read_manifest(path).await?;
+    serde_json::from_str(&contents).map_err(|e| parse_error(path, e))
+}
+
+/// Extract the dependencies on local projects declared by a manifest.
+///
+/// Relative paths are resolved against the directory containing the manifest. Cargo
+/// dependencies inherited with `workspace = true` are looked up in the nearest ancestor
+/// manifest declaring a `[workspace]`.
+///
+/// # Arguments
+///
+/// - `manifest`: Path to a `Cargo.toml` or `package.json` file.
+///
+/// # Returns
+///
+/// The list of local dependencies, or an empty list for unsupported manifests.
+pub async fn local_dependencies(manifest: &Path) -> Result<Vec<LocalDependency>> {
+    let Some(dir) = manifest.parent() else {
+        return Ok(Vec::new());
+    };
+
+    match manifest.file_name().and_then(|name| name.to_str()) {
+        Some("Cargo.toml") => {
+            let table = read_cargo_toml(manifest).await?;
+            let mut dependencies = Vec::new();
+            for deps in cargo_dependency_tables(&table) {
+                for (name, spec) in deps {
+                    if let Some(path) = spec.get("path").and_then(TomlValue::as_str) {
+                        dependencies.push(LocalDependency::Path(dir.join(path)));
+                    } else if spec.get("workspace").and_then(TomlValue::as_bool) == Some(true)
+                        && let Some(path) = cargo_workspace_dependency(dir, name).await?
+                    {
+                        dependencies.push(LocalDependency::Path(path));
+                    }
+                }
+            }
+            Ok(dependencies)
+        }
+        Some("package.json") => {
+            let json = read_package_json(manifest).await?;
+            let dependencies = NPM_DEPENDENCY_FIELDS
+                .iter()
+                .filter_map(|field| json.get(field).and_then(JsonValue::as_object))
+                .flatten()
+                .filter_map(|(name, spec)| {
+                    let spec = spec.as_str()?;
+                    if spec.starts_with("workspace:") {
+                        Some(LocalDependency::Workspace(name.clone()))
+                    } else {
+                        spec.strip_prefix("file:")
+                            .or_else(|| spec.strip_prefix("link:"))
+                            .map(|path| LocalDependency::Path(dir.join(path)))
+                    }
+                })
+                .collect();
+            Ok(dependencies)
+        }
+        _ => Ok(Vec::new()),
+    }
+}
+
+/// Read the `name` field of a `package.json`, if any.
+pub async fn package_name(manifest: &Path) -> Result<Option<String>> {
+    let json = read_package_json(manifest).await?;
+    Ok(json
+        .get("name")
+        .and_then(JsonValue::as_str)
+        .map(str::to_string))
+}
+
+/// Collect every dependency table of a Cargo manifest, including target-specific ones.
+fn cargo_dependency_tables(table: &Table) -> Vec<&Table> {
+    let mut tables = CARGO_DEPENDENCY_TABLES
+        .iter()
+        .filter_map(|key| table.get(*key).and_then(TomlValue::as_table))
+        .collect::<Vec<_>>();
+
+    if let Some(targets) = table.get("target").and_then(TomlValue::as_table) {
+        for target in targets.values().filter_map(TomlValue::as_table) {
+            tables.extend(
+                CARGO_DEPENDENCY_TABLES
+                    .iter()
+                    .filter_map(|key| target.get(*key).and_then(TomlValue::as_table)),
+            );
+        }
+    }
+
+    tables
+}
+
+/// Look up the path of a dependency declared in `[workspace.dependencies]` of the
+/// nearest workspace manifest, starting with the manifest in `dir` itself.
+async fn cargo_workspace_dependency(dir: &Path, name: &str) -> Result<Option<PathBuf>> {
+    for ancestor in dir.ancestors() {
+        let cargo_toml = ancestor.join("Cargo.toml");
+        if !cargo_toml.is_file() {
+            continue;
+        }
+
+        let table = read_cargo_toml(&cargo_toml).await?;
+        let Some(workspace) = table.get("workspace") else {
+            continue;
+        };
+
+        return Ok(workspace
+            .get("dependencies")
+            .and_then(|deps| deps.get(name))
+            .and_then(|spec| spec.get("path"))
+            .and_then(TomlValue::as_str)
+            .map(|path| ancestor.join(path)));
+    }
+
+    Ok(None)
+}
+
+/// Descriptive metadata declared in a project's manifest files.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Metadata {
+    pub name: Option<String>,
+    pub version: Option<String>,
+    pub description: Option<String>,
+    pub authors: Vec<String>,
+    pub license: Option<String>,
+}
+
+impl Metadata {
+    /// Fill in the fields that are still missing from another source.
+    fn merge(&mut self, other: Self) {
+        self.name = self.name.take().or(other.name);
+        self.version = self.version.take().or(other.version);
+        self.description = self.description.take().or(other.description);
+        self.license = self.license.take().or(other.license);
+        if self.authors.is_empty() {
+            self.authors = other.authors;
+        }
+    }
+}
+
+/// Manifests metadata is read from, in order of precedence.
+const METADATA_MANIFESTS: [&str; 6] = [
+    "Cargo.toml",
+    "package.json",
+    "pyproject.toml",
+    "deno.json",
+    "deno.jsonc",
+    "go.mod",
+];
+
+/// Read the metadata declared by the manifests directly inside `dir`.
+///
+/// When several manifests are present, fields are taken from the first one declaring
+/// them, following the order of [`METADATA_MANIFESTS`]. Manifests that cannot be parsed
+/// are skipped.
+pub async fn read_metadata(dir: &Path) -> Metadata {
+    let mut metadata = Metadata::default();
+
+    for name in METADATA_MANIFESTS {
+        let manifest = dir.join(name);
+        if !manifest.is_file() {
+            continue;
+        }
+
+        let parsed = match name {
+            "Cargo.toml" => match read_cargo_toml(&manifest).await {
+                Ok(table) => {
+                    let workspace = cargo_workspace_table(dir, &table).await;
+                    Ok(cargo_metadata(&table, workspace.as_ref()))
+                }
+                Err(e) => Err(e),
+            },
+            "package.json" | "deno.json" => read_package_json(&manifest)
+                .await
+                .map(|json| json_metadata(&json)),
+            "deno.jsonc" => read_manifest(&manifest).await.and_then(|contents| {
+                serde_json::from_str(&strip_json_comments(&contents))
+                    .map(|json| json_metadata(&json))
+                    .map_err(|e| parse_error(&manifest, e))
+            }),
+            "pyproject.toml" => read_manifest(&manifest).await.and_then(|contents| {
+                contents
+                    .parse::<Table>()
+                    .map(|table| pyproject_metadata(&table))
+                    .map_err(|e| parse_error(&manifest, e))
+            }),
+            "go.mod" => read_manifest(&manifest)
+                .await
+                .map(|contents| go_mod_metadata(&contents)),
+            _ => continue,
+        };
+
+        match parsed {
+            Ok(parsed) => metadata.merge(parsed),
+            Err(e) => warn!("Skipping metadata of {}: {e}", manifest.display()),
+        }
+    }
+
+    metadata
+}
+
+fn toml_string(value: Option<&TomlValue>) -> Option<String> {
+    value.and_then(TomlValue::as_str).map(str::to_string)
+}
+
+fn json_string(value: Option<&JsonValue>) -> Option<String> {
+    value.and_then(JsonValue::as_str).map(str::to_string)
+}
+
+/// Find the manifest of the workspace a crate belongs to: the crate's own manifest if it
+/// declares `[workspace]`, or the nearest ancestor doing so.
+async fn cargo_workspace_table(dir: &Path, table: &Table) -> Option<Table> {
+    if table.contains_key("workspace") {
+        return Some(table.clone());
+    }
+
+    for ancestor in dir.ancestors().skip(1) {
+        let cargo_toml = ancestor.join("Cargo.toml");
+        if !cargo_toml.is_file() {
+            continue;
+        }
+
+        if let Ok(table) = read_cargo_toml(&cargo_toml).await
+            && table.contains_key("workspace")
+        {
+            return Some(table);
+        }
+    }
+
+    None
+}
+
+fn cargo_metadata(table: &Table, workspace: Option<&Table>) -> Metadata {
+    let Some(package) = table.get("package") else {
+        return Metadata::default();
+    };
+    let workspace_package = workspace
+        .and_then(|workspace| workspace.get("workspace"))
+        .and_then(|workspace| workspace.get("package"));
+
+    // Fields set with `field.workspace = true` are inherited from `[workspace.package]`.
+    let field = |key: &str| {
+        let value = package.get(key)?;
+        if value.get("workspace").and_then(TomlValue::as_bool) == Some(true) {
+            workspace_package?.get(key)
+        } else {
+            Some(value)
+        }
+    };
+
+    Metadata {
+        name: toml_string(field("name")),
+        version: toml_string(field("version")),
+        description: toml_string(field("description")),
+        authors: field("authors")
+            .and_then(TomlValue::as_array)
+            .map(|authors| {
+                authors
+                    .iter()
+                    .filter_map(TomlValue::as_str)
+                    .map(str::to_string)
+                    .collect()
+            })
+            .unwrap_or_default(),
+        license: toml_string(field("license")),
+    }
+}
+
+/// Format a `package.json` person, given either as a string or as an object.
+fn json_person(person: &JsonValue) -> Option<String> {
+    match person {
+        JsonValue::String(person) => Some(person.clone()),
+        JsonValue::Object(person) => {
+            let name = person.get("name")?.as_str()?;
+            let email = person.get("email").and_then(JsonValue::as_str);
+            Some(email.map_or_else(|| name.to_string(), |email| format!("{name} <{email}>")))
+        }
+        _ => None,
+    }
+}
+
+/// Read metadata from `package.json` or `deno.json`.
+fn json_metadata(json: &JsonValue) -> Metadata {
+    let mut authors = json
+        .get("author")
+        .and_then(json_person)
+        .into_iter()
+        .collect::<Vec<_>>();
+    if let Some(contributors) = json.get("contributors").and_then(JsonValue::as_array) {
+        authors.extend(contributors.iter().filter_map(json_person));
+    }
+
+    let license = json.get("license").and_then(|license| match license {
+        JsonValue::String(license) => Some(license.clone()),
+        JsonValue::Object(license) => json_string(license.get("type")),
+        _ => None,
+    });
+
+    Metadata {
+        name: json_string(json.get("name")),
+        version: json_string(json.get("version")),
+        description: json_string(json.get("description")),
+        authors,
+        license,
+    }
+}
+
+/// Read metadata from the `[project]` table of `pyproject.toml`, falling back to
+/// `[tool.poetry]`.
+fn pyproject_metadata(table: &Table) -> Metadata {
+    let project = table.get("project");
+    let poetry = table.get("tool").and_then(|tool| tool.get("poetry"));
+    let field = |key: &str| {
+        project
+            .and_then(|project| project.get(key))
+            .or_else(|| poetry.and_then(|poetry| poetry.get(key)))
+    };
+
+    let authors = field("authors")
+        .and_then(TomlValue::as_array)
+        .map(|authors| {
+            authors
+                .iter()
+                .filter_map(|author| match author {
+                    TomlValue::String(author) => Some(author.clone()),
+                    TomlValue::Table(author) => {
+                        let name = author.get("name").and_then(TomlValue::as_str);
+                        let email = author.get("email").and_then(TomlValue::as_str);
+                        match (name, email) {
+                            (Some(name), Some(email)) => Some(format!("{name} <{email}>")),
+                            (Some(person), None) | (None, Some(person)) => Some(person.to_string()),
+                            (None, None) => None,
+                        }
+                    }
+                    _ => None,
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let license = field("license").and_then(|license| match license {
+        TomlValue::String(license) => Some(license.clone()),
+        TomlValue::Table(license) => toml_string(license.get("text")),
+        _ => None,
+    });
+
+    Metadata {
+        name: toml_string(field("name")),
+        version: toml_string(field("version")),
+        description: toml_string(field("description")),
+        authors,
+        license,
+    }
+}
+
+/// Read the module path declared in `go.mod`.
+fn go_mod_metadata(contents: &str) -> Metadata {
+    let name = contents.lines().find_map(|line| {
+        let module = line.trim().strip_prefix("module")?;
+        // The keyword is followed by whitespace, and the path by an optional comment.
+        let module = module.strip_prefix(char::is_whitespace)?;
+        let module = module.split("//").next()?.trim().trim_matches('"');
+        (!module.is_empty()).then(|| module.to_string())
+    });
+
+    Metadata {
+        name,
+        ..Metadata::default()
+    }
+}
+
+/// Remove `//` and `/* */` comments from JSONC, leaving string contents untouched.
+fn strip_json_comments(contents: &str) -> String {
+    let mut output = String::with_capacity(contents.len());
+    let mut chars = contents.chars().peekable();
+    let mut in_string = false;
+
+    while let Some(c) = chars.next() {
+        if in_string {
+            output.push(c);
+            match c {
+                '\\' => output.extend(chars.next()),
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+
+        match (c, chars.peek()) {
+            ('"', _) => {
+                in_string = true;
+                output.push(c);
+            }
+            ('/', Some('/')) => {
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        output.push('\n');
+                        break;
+                    }
+                }
+            }
+            ('/', Some('*')) => {
+                chars.next();
+                let mut previous = '\0';
+                for c in chars.by_ref() {
+                    if previous == '*' && c == '/' {
+                        break;
+                    }
+                    previous = c;
+                }
+            }
+            _ => output.push(c),
+        }
+    }
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{fs, io};
+    use tempfile::tempdir;
+
+    fn toml(contents: &str) -> Table {
+        match contents.parse() {
+            Ok(table) => table,
+            Err(e) => panic!("invalid TOML: {e}"),
+        }
+    }
+
+    fn json(contents: &str) -> JsonValue {
+        match serde_json::from_str(contents) {
+            Ok(json) => json,
+            Err(e) => panic!("invalid JSON: {e}"),
+        }
+    }
+
+    #[test]
+    fn go_mod_requires_whitespace_after_keyword() {
+        let name = |contents| go_mod_metadata(contents).name;
+
+        assert_eq!(
+            name("module example.com/app\n\ngo 1.22\n").as_deref(),
+            Some("example.com/app")
+        );
+        assert_eq!(
+            name("module\t\"example.com/app\" // the app\n").as_deref(),
+            Some("example.com/app")
+        );
+        assert_eq!(name("modulex example.com/app\n"), None);
+        assert_eq!(name("module\n"), None);
+    }
+
+    #[test]
+    fn cargo_fields_are_inherited_from_the_workspace() {
+        let workspace = toml(
+            r#"
+            [workspace.package]
+            version = "1.2.0"
+            authors = ["Ada"]
+            license = "MIT"
+            "#,
+        );
+        let member = toml(
+            r#"
+            [package]
+            name = "core"
+            version.workspace = true
+            authors = { workspace = true }
+            license = "Apache-2.0"
+            description.workspace = true
+            "#,
+        );
+
+        assert_eq!(
+            cargo_metadata(&member, Some(&workspace)),
+            Metadata {
+                name: Some("core".to_string()),
+                version: Some("1.2.0".to_string()),
+                description: None,
+                authors: vec!["Ada".to_string()],
+                license: Some("Apache-2.0".to_string()),
+            }
+        );
+        assert_eq!(cargo_metadata(&member, None).version, None);
+    }
+
+    #[tokio::test]
+    async fn cargo_workspace_is_found_in_an_ancestor() -> io::Result<()> {
+        let dir = tempdir()?;
+        let member = dir.path().join("crates/core");
+        fs::create_dir_all(&member)?;
+        fs::write(
+            dir.path().join("Cargo.toml"),
+            "[workspace]\nmembers = [\"crates/*\"]\n\n[workspace.package]\nversion = \"0.3.1\"\n",
+        )?;
+        fs::write(
+            member.join("Cargo.toml"),
+            "[package]\nname = \"core\"\nversion.workspace = true\n",
+        )?;
+
+        let metadata = read_metadata(&member).await;
+        assert_eq!(metadata.name.as_deref(), Some("core"));
+        assert_eq!(metadata.version.as_deref(), Some("0.3.1"));
+        Ok(())
+    }
+
+    #[test]
+    fn package_json_people_and_license() {
+        let metadata = json_metadata(&json(
+            r#"{
+                "name": "@acme/web",
+                "version": "2.0.0",
+                "author": { "name": "Ada", "email": "ada@example.com" },
+                "contributors": ["Grace", { "name": "Alan" }, 42],
+                "license": { "type": "ISC" }
+            }"#,
+        ));
+
+        assert_eq!(metadata.name.as_deref(), Some("@acme/web"));
+        assert_eq!(metadata.version.as_deref(), Some("2.0.0"));
+        assert_eq!(metadata.authors, ["Ada <ada@example.com>", "Grace", "Alan"]);
+        assert_eq!(metadata.license.as_deref(), Some("ISC"));
+    }
+
+    #[test]
+    fn deno_jsonc_comments_are_stripped_outside_strings() {
+        let contents = r#"{
+            // The package name
+            "name": "@acme/deno", /* inline */
+            "description": "not // a comment, nor /* this */",
+            "version": "0.1.0"
+        }"#;
+        let metadata = json_metadata(&json(&strip_json_comments(contents)));
+
+        assert_eq!(metadata.name.as_deref(), Some("@acme/deno"));
+        assert_eq!(
+            metadata.description.as_deref(),
+            Some("not // a comment, nor /* this */")
+        );
+        assert_eq!(metadata.version.as_deref(), Some("0.1.0"));
+    }
+
+    #[test]
+    fn pyproject_prefers_project_over_poetry() {
+        let metadata = pyproject_metadata(&toml(
+            r#"
+            [project]
+            name = "tool"
+            authors = [{ name = "Ada", email = "ada@example.com" }, { email = "ci@example.com" }]
+            license = { text = "MIT" }
+
+            [tool.poetry]
+            name = "legacy-name"
+            version = "0.9.0"
+            description = "From Poetry"
+            "#,
+        ));
+
+        assert_eq!(metadata.name.as_deref(), Some("tool"));
+        // Fields missing from `[project]` still come from `[tool.poetry]`.
+        assert_eq!(metadata.version.as_deref(), Some("0.9.0"));
+        assert_eq!(metadata.description.as_deref(), Some("From Poetry"));
+        assert_eq!(
+            metadata.authors,
+            ["Ada <ada@example.com>", "ci@example.com"]
+        );
+        assert_eq!(metadata.license.as_deref(), Some("MIT"));
+    }
+
+    #[test]
+    fn poetry_alone_is_enough() {
+        let metadata = pyproject_metadata(&toml(
+            r#"
+            [tool.poetry]
+            name = "app"
+            authors = ["Grace <grace@example.com>"]
+            "#,
+        ));
+
+        assert_eq!(metadata.name.as_deref(), Some("app"));
+        assert_eq!(metadata.authors, ["Grace <grace@example.com>"]);
+    }
+}
@@ -0,0 +1,407 @@
+use crate::{
+    config::Config,
+    duplicates::DuplicateGroup,
+    fuzzy::Hit,
+    git::{GitInfo, GitStatus, LastCommit, Remote, Unpushed},
+    manifest::Metadata,
+    project::Project,
+    template::{Template, TemplateError},
+};
+use serde::Serialize;
+use std::{
+    ffi::OsStr,
+    io::{self, Write},
+    path::Path,
+    str::FromStr,
+};
+
+/// Version of the JSON output schema.
+///
+/// Adding fields is not a breaking change and keeps the version. Removing, renaming or
+/// changing the meaning of a field bumps it.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// Escape sequences around highlighted characters: bold red.
+const HIGHLIGHT: &str = "\x1b[1;31m";
+const RESET: &str = "\x1b[0m";
+
+/// How discovered projects are printed.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// One project path per line
+    #[default]
+    Text,
+    /// A single JSON document
+    Json,
+    /// One JSON object per line
+    Ndjson,
+    /// One line per project rendered from a user-defined template
+    Template(Template),
+}
+
+impl FromStr for OutputFormat {
+    type Err = TemplateError;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(Self::Text),
+            "json" => Ok(Self::Json),
+            "ndjson" => Ok(Self::Ndjson),
+            template => {
+                let template = template
+                    .parse::<Template>()
+                    .map_err(|e| TemplateError(format!("invalid template: {e}")))?;
+                if !template.has_fields() {
+                    return Err(TemplateError(format!(
+                        "unknown format '{s}' (expected text, json, ndjson or a template \
+                         such as '{{name}}\\t{{path}}')"
+                    )));
+                }
+                Ok(Self::Template(template))
+            }
+        }
+    }
+}
+
+/// JSON document printed by `--format json`.
+#[derive(Debug, Serialize)]
+struct JsonDocument {
+    schema_version: u32,
+    projects: Vec<ProjectRecord>,
+}
+
+/// A project as it appears in JSON output.
+#[derive(Debug, Serialize)]
+struct ProjectRecord {
+    /// Only set for NDJSON, where each line stands on its own.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    schema_version: Option<u32>,
+    root: String,
+    name: String,
+    kinds: Vec<&'static str>,
+    markers: Vec<String>,
+    members: Vec<String>,
+    workspace_parent: Option<String>,
+    vcs_root: Option<String>,
+    vcs: Vec<&'static str>,
+    git_root: Option<String>,
+    bare: bool,
+    main_worktree: Option<String>,
+    worktrees: Vec<String>,
+    superproject: Option<String>,
+    submodules: Vec<String>,
+    depth: usize,
+    metadata: Metadata,
+    git: Option<GitInfo>,
+    remotes: Vec<Remote>,
+    /// Only present when the working tree status was requested.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    status: Option<GitStatus>,
+    /// Only present for `unpushed`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    unpushed: Option<Unpushed>,
+    /// Only present for `duplicates`.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    root_commits: Vec<String>,
+    /// Only present for `duplicates` and `--sort last-commit`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    last_commit: Option<LastCommit>,
+    /// Only present for `--sort frecency`, if the project was visited.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    frecency: Option<f64>,
+}
+
+/// Convert a path for JSON output, replacing bytes that are not valid UTF-8 with U+FFFD
+/// as JSON strings cannot hold them.
+fn path_string(path: &Path) -> String {
+    path.to_string_lossy().into_owned()
+}
+
+impl ProjectRecord {
+    fn new(project: &Project, schema_version: Option<u32>) -> Self {
+        Self {
+            schema_version,
+            root: path_string(&project.root),
+            name: project.name().to_string_lossy().into_owned(),
+            kinds: project.kinds.iter().map(|kind| kind.as_str()).collect(),
+            markers: project
+                .markers
+                .iter()
+                .map(|path| path_string(path))
+                .collect(),
+            members: project
+                .members
+                .iter()
+                .map(|path| path_string(path))
+                .collect(),
+            workspace_parent: project.workspace_parent.as_deref().map(path_string),
+            vcs_root: project.vcs_root.as_deref().map(path_string),
+            vcs: project.vcs.iter().map(|vcs| vcs.as_str()).collect(),
+            git_root: project.git_root.as_deref().map(path_string),
+            bare: project.bare,
+            main_worktree: project.main_worktree.as_deref().map(path_string),
+            worktrees: project
+                .worktrees
+                .iter()
+                .map(|path| path_string(path))
+                .collect(),
+            superproject: project.superproject.as_deref().map(path_string),
+            submodules: project
+                .submodules
+                .iter()
+                .map(|path| path_string(path))
+                .collect(),
+            depth: project.depth,
+            metadata: project.metadata.clone(),
+            git: project.git.clone(),
+            remotes: project.remotes.clone(),
+            status: project.status.clone(),
+            unpushed: project.unpushed.clone(),
+            root_commits: project.root_commits.clone(),
+            last_commit: project.last_commit.clone(),
+            frecency: project.frecency,
+        }
+    }
+}
+
+/// JSON document printed by `duplicates --format json`.
+#[derive(Debug, Serialize)]
+struct DuplicatesDocument {
+    schema_version: u32,
+    duplicates: Vec<DuplicateRecord>,
+}
+
+/// A group of clones as it appears in JSON output.
+#[derive(Debug, Serialize)]
+struct DuplicateRecord {
+    /// Only set for NDJSON, where each line stands on its own.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    schema_version: Option<u32>,
+    remote: Option<String>,
+    root_commit: Option<String>,
+    projects: Vec<ProjectRecord>,
+}
+
+impl DuplicateRecord {
+    fn new(group: &DuplicateGroup, schema_version: Option<u32>) -> Self {
+        Self {
+            schema_version,
+            remote: group.remote.clone(),
+            root_commit: group.root_commit.clone(),
+            projects: group
+                .projects
+                .iter()
+                .map(|project| ProjectRecord::new(project, None))
+                .collect(),
+        }
+    }
+}
+
+/// Write an `OsStr` without any lossy conversion.
+///
+/// On Unix the raw bytes are written, so non-UTF-8 paths survive intact. Elsewhere the
+/// string is converted lossily.
+pub fn write_os_str(out: &mut impl Write, s: &OsStr) -> io::Result<()> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::ffi::OsStrExt;
+        out.write_all(s.as_bytes())
+    }
+    #[cfg(not(unix))]
+    {
+        out.write_all(s.to_string_lossy().as_bytes())
+    }
+}
+
+/// Print a single path followed by a newline, written the same way as project roots.
+///
+/// # Errors
+///
+/// Returns an error if writing to stdout fails.
+pub fn print_path(path: &Path) -> io::Result<()> {
+    let mut stdout = io::stdout().lock();
+    write_os_str(&mut stdout, path.as_os_str())?;
+    writeln!(stdout)
+}
+
+/// Print projects to stdout in the format selected by the configuration.
+///
+/// Each record of line-based formats is terminated by a newline, or by NUL with
+/// `--print0`. With `--git-status`, text output appends the status summary of each
+/// project after a tab, followed by the unpushed branches for `unpushed`.
+///
+/// # Errors
+///
+/// Returns an error if writing to stdout or serializing a project fails.
+pub fn print_projects(projects: &[Project], config: &Config) -> io::Result<()> {
+    let terminator = if config.print0 { b'\0' } else { b'\n' };
+    let mut stdout = io::stdout().lock();
+
+    match &config.format {
+        OutputFormat::Text => {
+            for project in projects {
+                write_os_str(&mut stdout, project.root.as_os_str())?;
+                if config.git_status {
+                    match &project.status {
+                        Some(status) => write!(stdout, "\t{status}")?,
+                        None => write!(stdout, "\t-")?,
+                    }
+                }
+                if let Some(unpushed) = &project.unpushed {
+                    write!(stdout, "\t{unpushed}")?;
+                }
+                stdout.write_all(&[terminator])?;
+            }
+        }
+        OutputFormat::Json => {
+            let document = JsonDocument {
+                schema_version: SCHEMA_VERSION,
+                projects: projects
+                    .iter()
+                    .map(|project| ProjectRecord::new(project, None))
+                    .collect(),
+            };
+            serde_json::to_writer_pretty(&mut stdout, &document)?;
+            writeln!(stdout)?;
+        }
+        OutputFormat::Ndjson => {
+            for project in projects {
+                serde_json::to_writer(
+                    &mut stdout,
+                    &ProjectRecord::new(project, Some(SCHEMA_VERSION)),
+                )?;
+                stdout.write_all(&[terminator])?;
+            }
+        }
+        OutputFormat::Template(template) => {
+            for project in projects {
+                write_os_str(&mut stdout, &template.render(project))?;
+                stdout.write_all(&[terminator])?;
+            }
+        }
+    }
+
+    stdout.flush()
+}
+
+/// Print the projects matching a `find` query, best first.
+///
+/// Text output highlights the matched characters of each path if `highlight` is set,
+/// other formats print the projects as usual.
+///
+/// # Errors
+///
+/// Returns an error if writing to stdout or serializing a project fails.
+pub fn print_matches(hits: &[Hit], config: &Config, highlight: bool) -> io::Result<()> {
+    if config.format != OutputFormat::Text || !highlight {
+        let projects = hits
+            .iter()
+            .map(|hit| hit.project.clone())
+            .collect::<Vec<_>>();
+        return print_projects(&projects, config);
+    }
+
+    let terminator = if config.print0 { '\0' } else { '\n' };
+    let mut stdout = io::stdout().lock();
+    for hit in hits {
+        let root = hit.project.root.to_string_lossy();
+        for (i, c) in root.chars().enumerate() {
+            if hit.positions.binary_search(&i).is_ok() {
+                write!(stdout, "{HIGHLIGHT}{c}{RESET}")?;
+            } else {
+                write!(stdout, "{c}")?;
+            }
+        }
+        if config.git_status {
+            match &hit.project.status {
+                Some(status) => write!(stdout, "\t{status}")?,
+                None => write!(stdout, "\t-")?,
+            }
+        }
+        write!(stdout, "{terminator}")?;
+    }
+
+    stdout.flush()
+}
+
+/// Print groups of duplicate clones to stdout in the format selected by the
+/// configuration.
+///
+/// Text output starts each group with the shared remote, or the shared root commit,
+/// followed by one indented line per clone with its branch, last commit date and
+/// working tree state. Templates are rendered once per clone, with an empty record
+/// between groups.
+///
+/// # Errors
+///
+/// Returns an error if writing to stdout or serializing a group fails.
+pub fn print_duplicates(groups: &[DuplicateGroup], config: &Config) -> io::Result<()> {
+    let terminator = if config.print0 { b'\0' } else { b'\n' };
+    let mut stdout = io::stdout().lock();
+
+    match &config.format {
+        OutputFormat::Text => {
+            for group in groups {
+                match (&group.remote, &group.root_commit) {
+                    (Some(remote), _) => write!(stdout, "{remote}")?,
+                    (None, Some(root_commit)) => write!(stdout, "root commit {root_commit}")?,
+                    (None, None) => write!(stdout, "-")?,
+                }
+                stdout.write_all(&[terminator])?;
+
+                for project in &group.projects {
+                    let git = project.git.as_ref();
+                    let branch = git
+                        .and_then(|git| git.branch.as_deref().or(git.head.as_deref()))
+                        .unwrap_or("-");
+                    let last_commit = project
+                        .last_commit
+                        .as_ref()
+                        .map_or("-", |last_commit| last_commit.date.as_str());
+                    let state = project
+                        .status
+                        .as_ref()
+                        .map_or("-", |status| if status.dirty { "dirty" } else { "clean" });
+
+                    stdout.write_all(b"\t")?;
+                    write_os_str(&mut stdout, project.root.as_os_str())?;
+                    write!(stdout, "\t{branch}\t{last_commit}\t{state}")?;
+                    stdout.write_all(&[terminator])?;
+                }
+            }
+        }
+        OutputFormat::Json => {
+            let document = DuplicatesDocument {
+                schema_version: SCHEMA_VERSION,
+                duplicates: groups
+                    .iter()
+                    .map(|group| DuplicateRecord::new(group, None))
+                    .collect(),
+            };
+            serde_json::to_writer_pretty(&mut stdout, &document)?;
+            writeln!(stdout)?;
+        }
+        OutputFormat::Ndjson => {
+            for group in groups {
+                serde_json::to_writer(
+                    &mut stdout,
+                    &DuplicateRecord::new(group, Some(SCHEMA_VERSION)),
+                )?;
+                stdout.write_all(&[terminator])?;
+            }
+        }
+        OutputFormat::Template(template) => {
+            for (i, group) in groups.iter().enumerate() {
+                if i > 0 {
+                    stdout.write_all(&[terminator])?;
+                }
+                for project in &group.projects {
+                    write_os_str(&mut stdout, &template.render(project))?;
+                    stdout.write_all(&[terminator])?;
+                }
+            }
+        }
+    }
+
+    stdout.flush()
+}
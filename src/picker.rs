@@ -0,0 +1,486 @@
+use crate::{
+    config::Config,
+    errors::{ProjectFinderError, Result},
+    finder::ProjectFinder,
+    fuzzy::match_project,
+    project::Project,
+};
+use crossterm::{
+    cursor::{Hide, MoveTo, Show},
+    event::{self, Event, KeyCode, KeyEvent, KeyModifiers},
+    execute, queue,
+    style::{Attribute, Color, Print, ResetColor, SetAttribute, SetForegroundColor},
+    terminal::{
+        self, Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode,
+        enable_raw_mode,
+    },
+};
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    io::{self, Stderr, Write},
+    path::{Path, PathBuf},
+    slice,
+    time::Duration,
+};
+use tokio::{
+    sync::mpsc::{UnboundedSender, unbounded_channel},
+    task::{block_in_place, spawn},
+};
+
+/// How long to wait for a key before looking for newly discovered projects.
+const REFRESH_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Number of lines of the README shown in the preview.
+const README_LINES: usize = 40;
+
+/// The terminal switched to the alternate screen in raw mode, restored when dropped.
+///
+/// The picker draws on stderr, so stdout only receives the chosen path and the picker
+/// works inside `$(...)`.
+struct Screen {
+    out: Stderr,
+}
+
+impl Screen {
+    fn enter() -> io::Result<Self> {
+        enable_raw_mode()?;
+        let mut out = io::stderr();
+        execute!(out, EnterAlternateScreen, Hide)?;
+        Ok(Self { out })
+    }
+}
+
+impl Drop for Screen {
+    fn drop(&mut self) {
+        let _ = execute!(self.out, Show, LeaveAlternateScreen);
+        let _ = disable_raw_mode();
+    }
+}
+
+/// What a key press asks the picker to do.
+enum Action {
+    Continue,
+    Pick(PathBuf),
+    Cancel,
+}
+
+/// State of the picker: the projects found so far, the query and the selection.
+#[derive(Default)]
+struct Picker {
+    projects: Vec<Project>,
+    /// Roots of the projects added while discovery is running.
+    streamed: HashSet<PathBuf>,
+    /// Indices of the projects matching the query, best first, with the matched
+    /// characters of their roots.
+    matches: Vec<(usize, Vec<usize>)>,
+    query: String,
+    selected: usize,
+    /// Index of the first match shown in the list.
+    offset: usize,
+    searching: bool,
+    previews: HashMap<PathBuf, Vec<String>>,
+    /// Roots of the projects whose preview is being prepared.
+    pending_previews: HashSet<PathBuf>,
+    /// Whether the screen has to be drawn again.
+    changed: bool,
+}
+
+impl Picker {
+    fn selected_project(&self) -> Option<&Project> {
+        let &(index, _) = self.matches.get(self.selected)?;
+        self.projects.get(index)
+    }
+
+    /// Replace the projects, keeping the selected one selected if it is still there.
+    fn set_projects(&mut self, projects: Vec<Project>) {
+        if projects != self.projects {
+            let selected = self.selected_root();
+            self.projects = projects;
+            self.rematch(selected);
+        }
+    }
+
+    /// Add projects discovered since the last call, keeping the list ordered by root
+    /// and the selected project selected.
+    fn add_projects(&mut self, projects: Vec<Project>) {
+        if projects.is_empty() {
+            return;
+        }
+
+        let selected = self.selected_root();
+        self.streamed
+            .extend(projects.iter().map(|project| project.root.clone()));
+        self.projects.extend(projects);
+        self.projects.sort_by(|a, b| a.root.cmp(&b.root));
+        self.rematch(selected);
+    }
+
+    fn selected_root(&self) -> Option<PathBuf> {
+        self.selected_project().map(|project| project.root.clone())
+    }
+
+    /// Match the projects against the query again, keeping the selection.
+    fn refilter(&mut self) {
+        let selected = self.selected_root();
+        self.rematch(selected);
+    }
+
+    /// Match the projects against the query, selecting the project rooted at
+    /// `selected` if it matches and the best match otherwise.
+    fn rematch(&mut self, selected: Option<PathBuf>) {
+        let terms = self.query.split_whitespace().collect::<Vec<_>>();
+        let mut scored = self
+            .projects
+            .iter()
+            .enumerate()
+            .filter_map(|(index, project)| {
+                let (score, positions) = match_project(&terms, project)?;
+                Some((score, index, positions))
+            })
+            .collect::<Vec<_>>();
+        scored.sort_by_key(|(score, _, _)| std::cmp::Reverse(*score));
+        self.matches = scored
+            .into_iter()
+            .map(|(_, index, positions)| (index, positions))
+            .collect();
+
+        self.changed = true;
+        self.selected = selected
+            .and_then(|root| {
+                self.matches
+                    .iter()
+                    .position(|&(index, _)| self.projects[index].root == root)
+            })
+            .unwrap_or(0);
+    }
+
+    /// Start preparing the preview of the selected project in the background, unless it
+    /// is ready or on its way.
+    fn request_preview(&mut self, finder: &ProjectFinder, previews: &UnboundedSender<Preview>) {
+        let Some(project) = self.selected_project().cloned() else {
+            return;
+        };
+        if self.previews.contains_key(&project.root)
+            || !self.pending_previews.insert(project.root.clone())
+        {
+            return;
+        }
+
+        let finder = finder.clone();
+        let previews = previews.clone();
+        spawn(async move {
+            let root = project.root.clone();
+            let lines = preview(&finder, project).await;
+            // The picker may be gone by now, then the preview is not needed anymore.
+            let _ = previews.send((root, lines));
+        });
+    }
+
+    fn move_selection(&mut self, delta: isize) {
+        let last = self.matches.len().saturating_sub(1);
+        self.selected = self.selected.saturating_add_signed(delta).min(last);
+    }
+
+    fn handle_key(&mut self, key: KeyEvent, page: usize) -> Action {
+        let control = key.modifiers.contains(KeyModifiers::CONTROL);
+        self.changed = true;
+        let page = isize::try_from(page).unwrap_or(isize::MAX);
+
+        match key.code {
+            KeyCode::Esc => return Action::Cancel,
+            KeyCode::Char('c' | 'g') if control => return Action::Cancel,
+            KeyCode::Enter => {
+                if let Some(project) = self.selected_project() {
+                    return Action::Pick(project.root.clone());
+                }
+            }
+            KeyCode::Up => self.move_selection(-1),
+            KeyCode::Char('p' | 'k') if control => self.move_selection(-1),
+            KeyCode::Down | KeyCode::Tab => self.move_selection(1),
+            KeyCode::Char('n' | 'j') if control => self.move_selection(1),
+            KeyCode::PageUp => self.move_selection(-page),
+            KeyCode::PageDown => self.move_selection(page),
+            KeyCode::Char('u') if control => {
+                self.query.clear();
+                self.refilter();
+            }
+            KeyCode::Backspace => {
+                self.query.pop();
+                self.refilter();
+            }
+            KeyCode::Char(c) if !control => {
+                self.query.push(c);
+                self.refilter();
+            }
+            _ => {}
+        }
+
+        Action::Continue
+    }
+
+    /// Draw the query line, the list of matches and the preview of the selected one.
+    fn draw(&mut self, out: &mut impl Write) -> io::Result<()> {
+        self.changed = false;
+        let (width, height) = terminal::size()?;
+        let (width, height) = (usize::from(width), usize::from(height));
+        let rows = height.saturating_sub(1);
+        // The preview only gets half of the screen if there is room for it.
+        let list_width = if width >= 60 { width / 2 } else { width };
+
+        if self.selected < self.offset {
+            self.offset = self.selected;
+        } else if self.selected >= self.offset + rows {
+            self.offset = self.selected + 1 - rows;
+        }
+
+        queue!(out, MoveTo(0, 0), Clear(ClearType::All))?;
+
+        let status = format!(
+            "{}/{}{}",
+            self.matches.len(),
+            self.projects.len(),
+            if self.searching { " searching…" } else { "" }
+        );
+        let prompt = truncate(
+            &format!("> {}", self.query),
+            width.saturating_sub(status.len() + 1),
+        );
+        queue!(
+            out,
+            Print(&prompt),
+            MoveTo(to_u16(width.saturating_sub(status.chars().count())), 0),
+            SetForegroundColor(Color::DarkGrey),
+            Print(&status),
+            ResetColor,
+        )?;
+
+        for (row, (index, positions)) in
+            self.matches.iter().skip(self.offset).take(rows).enumerate()
+        {
+            let selected = self.offset + row == self.selected;
+            let root = self.projects[*index].root.to_string_lossy();
+
+            queue!(out, MoveTo(0, to_u16(row + 1)))?;
+            if selected {
+                queue!(out, SetAttribute(Attribute::Reverse), Print("> "))?;
+            } else {
+                queue!(out, Print("  "))?;
+            }
+            let root = root.chars().map(printable);
+            for (i, c) in root.take(list_width.saturating_sub(3)).enumerate() {
+                if positions.binary_search(&i).is_ok() {
+                    queue!(
+                        out,
+                        SetForegroundColor(Color::Red),
+                        Print(c),
+                        SetForegroundColor(Color::Reset)
+                    )?;
+                } else {
+                    queue!(out, Print(c))?;
+                }
+            }
+            queue!(out, SetAttribute(Attribute::Reset))?;
+        }
+
+        if list_width < width {
+            // Previews are prepared in the background, only the root is shown until then.
+            let selected = self.selected_project();
+            let placeholder = selected
+                .map(|project| vec![project.root.to_string_lossy().into_owned()])
+                .unwrap_or_default();
+            let lines = selected
+                .and_then(|project| self.previews.get(&project.root))
+                .unwrap_or(&placeholder);
+            let preview_width = width - list_width - 2;
+            for row in 0..rows {
+                let line = lines.get(row).map_or("", String::as_str);
+                queue!(
+                    out,
+                    MoveTo(to_u16(list_width), to_u16(row + 1)),
+                    SetForegroundColor(Color::DarkGrey),
+                    Print("│ "),
+                    ResetColor,
+                    Print(truncate(line, preview_width)),
+                )?;
+            }
+        }
+
+        out.flush()
+    }
+}
+
+/// A prepared preview: the root of the project and the lines describing it.
+type Preview = (PathBuf, Vec<String>);
+
+/// Let the user pick a project interactively while discovery is still running.
+///
+/// Projects show up as soon as they are discovered, unordered and without their
+/// details, and are replaced by the filtered and sorted results once discovery is
+/// complete. Until then only the filters on project kinds apply, as the others need the
+/// details. Typing fuzzy filters the list like `find`, and the preview shows the kinds,
+/// Git branch and working tree state and the start of the README of the selected
+/// project, read in the background so the list stays responsive.
+///
+/// # Returns
+///
+/// The root of the chosen project, or `None` if the picker was cancelled.
+///
+/// # Errors
+///
+/// Returns an error if the terminal cannot be used or discovery fails.
+pub async fn pick(finder: &ProjectFinder, config: &Config) -> Result<Option<PathBuf>> {
+    let mut search = Some({
+        let finder = finder.clone();
+        spawn(async move { finder.find_projects().await })
+    });
+
+    let mut screen = Screen::enter()?;
+    let mut picker = Picker {
+        searching: true,
+        ..Picker::default()
+    };
+    let (preview_sender, mut preview_receiver) = unbounded_channel();
+
+    loop {
+        if let Some(handle) = search.take_if(|handle| handle.is_finished()) {
+            let projects = handle.await.map_err(|e| {
+                ProjectFinderError::CommandExecutionFailed(format!("Task panicked: {e}"))
+            })??;
+            picker.searching = false;
+            picker.changed = true;
+            picker.set_projects(projects);
+        } else if search.is_some() {
+            // Only the projects that are new are taken, as the list can be long.
+            let projects = finder
+                .discovered_so_far(|project| {
+                    !picker.streamed.contains(&project.root)
+                        && project.matches_kinds(&config.types, &config.exclude_types)
+                })
+                .await;
+            picker.add_projects(projects);
+        }
+
+        picker.request_preview(finder, &preview_sender);
+        while let Ok((root, lines)) = preview_receiver.try_recv() {
+            picker.pending_previews.remove(&root);
+            picker.previews.insert(root, lines);
+            picker.changed = true;
+        }
+
+        if picker.changed {
+            picker.draw(&mut screen.out)?;
+        }
+
+        if !block_in_place(|| event::poll(REFRESH_INTERVAL))? {
+            continue;
+        }
+        let key = match event::read()? {
+            Event::Key(key) if key.is_press() => key,
+            Event::Resize(..) => {
+                picker.changed = true;
+                continue;
+            }
+            _ => continue,
+        };
+
+        let page = usize::from(terminal::size()?.1).saturating_sub(1);
+        match picker.handle_key(key, page) {
+            Action::Continue => {}
+            Action::Pick(root) => return Ok(Some(root)),
+            Action::Cancel => return Ok(None),
+        }
+    }
+}
+
+/// Lines describing a project: its kinds, Git branch and working tree state, followed
+/// by the start of its README.
+async fn preview(finder: &ProjectFinder, mut project: Project) -> Vec<String> {
+    if project.git_root.is_none() && finder.enrich_project(&mut project).await.is_err() {
+        return vec![project.root.to_string_lossy().into_owned()];
+    }
+    if project.status.is_none() {
+        // Without `git` the state is simply left out.
+        let _ = finder
+            .read_git_statuses(slice::from_mut(&mut project))
+            .await;
+    }
+
+    let kinds = project
+        .kinds
+        .iter()
+        .map(|kind| kind.as_str())
+        .collect::<Vec<_>>();
+    let mut lines = vec![
+        project.root.to_string_lossy().into_owned(),
+        format!(
+            "kinds:  {}",
+            if kinds.is_empty() {
+                "-".to_string()
+            } else {
+                kinds.join(", ")
+            }
+        ),
+    ];
+
+    if let Some(git) = &project.git {
+        let branch = match (&git.branch, &git.head) {
+            (Some(branch), _) if !git.detached => branch.clone(),
+            (_, Some(head)) => format!("detached at {head}"),
+            _ => "-".to_string(),
+        };
+        lines.push(format!("branch: {branch}"));
+    }
+    if let Some(status) = &project.status {
+        lines.push(format!("status: {status}"));
+    }
+    if let Some(description) = &project.metadata.description {
+        lines.push(String::new());
+        lines.push(description.clone());
+    }
+
+    if let Some(readme) = read_readme(&project.root) {
+        lines.push(String::new());
+        lines.extend(
+            readme
+                .lines()
+                .take(README_LINES)
+                .map(|line| line.replace('\t', "    ")),
+        );
+    }
+
+    lines
+}
+
+/// Contents of the README at the root of a project, if it has one.
+fn read_readme(root: &Path) -> Option<String> {
+    let mut readmes = fs::read_dir(root)
+        .ok()?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            path.is_file()
+                && path
+                    .file_name()
+                    .and_then(|name| name.to_str())
+                    .is_some_and(|name| name.to_lowercase().starts_with("readme"))
+        })
+        .collect::<Vec<_>>();
+    readmes.sort();
+    fs::read_to_string(readmes.first()?).ok()
+}
+
+/// Cut a line to at most `width` characters, with control characters made visible.
+fn truncate(line: &str, width: usize) -> String {
+    line.chars().map(printable).take(width).collect()
+}
+
+/// Replace a control character, such as the escape starting an ANSI or OSC sequence,
+/// so text read from a project cannot move the cursor, recolor or retitle the
+/// terminal.
+fn printable(c: char) -> char {
+    if c.is_control() { '?' } else { c }
+}
+
+fn to_u16(value: usize) -> u16 {
+    u16::try_from(value).unwrap_or(u16::MAX)
+}
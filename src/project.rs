@@ -0,0 +1,233 @@
+use crate::{
+    git::{GitInfo, GitStatus, LastCommit, Remote, Unpushed},
+    manifest::Metadata,
+    vcs::Vcs,
+};
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeSet,
+    ffi::{OsStr, OsString},
+    fmt::{self, Display},
+    fs::canonicalize,
+    path::{Path, PathBuf},
+};
+
+/// The kind of a project, derived from the marker files found in it.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, ValueEnum, Serialize, Deserialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum ProjectKind {
+    Rust,
+    Node,
+    Deno,
+    Bun,
+    Go,
+    Python,
+    Cmake,
+    Make,
+    Just,
+}
+
+impl ProjectKind {
+    /// Determine the project kind indicated by a marker file name.
+    pub fn from_marker(marker_name: &str) -> Option<Self> {
+        Some(match marker_name {
+            "Cargo.toml" => Self::Rust,
+            "package.json" | "pnpm-workspace.yaml" | "lerna.json" => Self::Node,
+            "deno.json" | "deno.jsonc" => Self::Deno,
+            "bunfig.toml" => Self::Bun,
+            "go.mod" => Self::Go,
+            "pyproject.toml" => Self::Python,
+            "CMakeLists.txt" => Self::Cmake,
+            "Makefile" => Self::Make,
+            "justfile" | "Justfile" => Self::Just,
+            _ => return None,
+        })
+    }
+
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Rust => "rust",
+            Self::Node => "node",
+            Self::Deno => "deno",
+            Self::Bun => "bun",
+            Self::Go => "go",
+            Self::Python => "python",
+            Self::Cmake => "cmake",
+            Self::Make => "make",
+            Self::Just => "just",
+        }
+    }
+}
+
+impl Display for ProjectKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A discovered project.
+///
+/// Only what discovery finds is serialized, for the project index. The depth depends on
+/// the search path and the remaining details are read on demand.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Project {
+    /// Root directory of the project.
+    pub root: PathBuf,
+    /// Kinds detected from the marker files at the project root.
+    pub kinds: BTreeSet<ProjectKind>,
+    /// Marker files at the project root.
+    pub markers: BTreeSet<PathBuf>,
+    /// Marker files of the workspace members and sub-projects collapsed into the
+    /// project.
+    pub members: BTreeSet<PathBuf>,
+    /// Closest enclosing workspace root, if the project is nested in one.
+    pub workspace_parent: Option<PathBuf>,
+    /// Root of the closest repository of any version control system containing the
+    /// project.
+    pub vcs_root: Option<PathBuf>,
+    /// Version control systems with a working copy at `vcs_root`.
+    pub vcs: BTreeSet<Vcs>,
+    /// Root of the Git repository containing the project.
+    pub git_root: Option<PathBuf>,
+    /// Whether the project is a bare Git repository without a working tree.
+    pub bare: bool,
+    /// Main working tree of the Git repository, if it is a linked worktree.
+    pub main_worktree: Option<PathBuf>,
+    /// Linked worktrees of the Git repository.
+    pub worktrees: Vec<PathBuf>,
+    /// Repository declaring the Git repository as a submodule.
+    pub superproject: Option<PathBuf>,
+    /// Submodules declared by the Git repository.
+    pub submodules: Vec<PathBuf>,
+    /// Depth of the project root below the search path it was found in.
+    #[serde(skip)]
+    pub depth: usize,
+    /// Metadata declared in the manifests at the project root.
+    pub metadata: Metadata,
+    /// State of the Git repository containing the project.
+    pub git: Option<GitInfo>,
+    /// Remotes configured in the Git repository.
+    pub remotes: Vec<Remote>,
+    /// Working tree status of the Git repository, only read when requested.
+    #[serde(skip)]
+    pub status: Option<GitStatus>,
+    /// Local branches missing from the remotes, only read by `unpushed`.
+    #[serde(skip)]
+    pub unpushed: Option<Unpushed>,
+    /// Commits without parents in the Git repository, only read by `duplicates`.
+    #[serde(skip)]
+    pub root_commits: Vec<String>,
+    /// Latest commit of the Git repository, only read when needed.
+    #[serde(skip)]
+    pub last_commit: Option<LastCommit>,
+    /// Frecency score from the visit database, only read by `--sort frecency`.
+    #[serde(skip)]
+    pub frecency: Option<f64>,
+}
+
+impl Project {
+    /// Create a new project without any detected kinds.
+    pub fn new(root: PathBuf) -> Self {
+        Self {
+            root,
+            kinds: BTreeSet::new(),
+            markers: BTreeSet::new(),
+            members: BTreeSet::new(),
+            workspace_parent: None,
+            vcs_root: None,
+            vcs: BTreeSet::new(),
+            git_root: None,
+            bare: false,
+            main_worktree: None,
+            worktrees: Vec::new(),
+            superproject: None,
+            submodules: Vec::new(),
+            depth: 0,
+            metadata: Metadata::default(),
+            git: None,
+            remotes: Vec::new(),
+            status: None,
+            unpushed: None,
+            root_commits: Vec::new(),
+            last_commit: None,
+            frecency: None,
+        }
+    }
+
+    /// Record a marker file belonging to the project. Markers at the root add their
+    /// kind, the others belong to members.
+    pub fn add_marker(&mut self, marker: PathBuf) {
+        if marker.parent() != Some(&self.root) {
+            self.members.insert(marker);
+            return;
+        }
+
+        let kind = marker
+            .file_name()
+            .and_then(|name| name.to_str())
+            .and_then(ProjectKind::from_marker);
+        self.kinds.extend(kind);
+        self.markers.insert(marker);
+    }
+
+    /// Replace every path of the project, such as its root, markers and related
+    /// repositories, with the result of `f`.
+    pub fn map_paths(&mut self, f: impl Fn(&Path) -> PathBuf) {
+        let map_all = |paths: &mut Vec<PathBuf>| {
+            for path in paths {
+                *path = f(path);
+            }
+        };
+
+        self.root = f(&self.root);
+        self.markers = self.markers.iter().map(|marker| f(marker)).collect();
+        self.members = self.members.iter().map(|member| f(member)).collect();
+        for path in [
+            &mut self.workspace_parent,
+            &mut self.vcs_root,
+            &mut self.git_root,
+            &mut self.main_worktree,
+            &mut self.superproject,
+        ]
+        .into_iter()
+        .flatten()
+        {
+            *path = f(path);
+        }
+        map_all(&mut self.worktrees);
+        map_all(&mut self.submodules);
+    }
+
+    /// The project name, taken from the name of its root directory.
+    pub fn name(&self) -> OsString {
+        self.root
+            .file_name()
+            .map(OsStr::to_os_string)
+            .or_else(|| {
+                canonicalize(&self.root)
+                    .ok()?
+                    .file_name()
+                    .map(OsStr::to_os_string)
+            })
+            .unwrap_or_default()
+    }
+
+    /// The main remote of the Git repository: `origin` if it exists, the first remote
+    /// otherwise.
+    pub fn remote(&self) -> Option<&Remote> {
+        self.remotes
+            .iter()
+            .find(|remote| remote.name == "origin")
+            .or_else(|| self.remotes.first())
+    }
+
+    /// Check whether the project passes the `--type` and `--not-type` filters.
+    pub fn matches_kinds(&self, include: &[ProjectKind], exclude: &[ProjectKind]) -> bool {
+        let included = include.is_empty() || include.iter().any(|kind| self.kinds.contains(kind));
+        let excluded = exclude.iter().any(|kind| self.kinds.contains(kind));
+        included && !excluded
+    }
+}
@@ -0,0 +1,70 @@
+use crate::project::Project;
+use clap::ValueEnum;
+use std::{cmp::Reverse, fs::metadata, path::PathBuf, time::SystemTime};
+
+/// Order in which projects are listed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum SortOrder {
+    /// By root path
+    #[default]
+    Path,
+    /// By name, then path
+    Name,
+    /// Most recently modified marker file or root directory first
+    Mtime,
+    /// Most recent Git commit first
+    LastCommit,
+    /// Most frequently and recently visited first, see `visit`
+    Frecency,
+}
+
+/// Latest modification time among the marker files and the root directory of a project.
+fn modified(project: &Project) -> Option<SystemTime> {
+    std::iter::once(&project.root)
+        .chain(&project.markers)
+        .chain(&project.members)
+        .filter_map(|path| metadata(path).ok()?.modified().ok())
+        .max()
+}
+
+/// Sort key putting the newest values first and missing ones last, ties broken by root.
+fn newest_first<T: Ord>(
+    value: Option<T>,
+    project: &Project,
+) -> (bool, Reverse<Option<T>>, PathBuf) {
+    (value.is_none(), Reverse(value), project.root.clone())
+}
+
+/// Sort projects in the given order, reversed if requested.
+///
+/// Projects without the information the order is based on, such as a commit or a visit,
+/// come last. Ties are broken by root path.
+pub fn sort_projects(projects: &mut [Project], order: SortOrder, reverse: bool) {
+    match order {
+        SortOrder::Path => projects.sort_by(|a, b| a.root.cmp(&b.root)),
+        SortOrder::Name => {
+            projects.sort_by_cached_key(|project| (project.name(), project.root.clone()));
+        }
+        SortOrder::Mtime => {
+            projects.sort_by_cached_key(|project| newest_first(modified(project), project));
+        }
+        SortOrder::LastCommit => projects.sort_by_cached_key(|project| {
+            let timestamp = project
+                .last_commit
+                .as_ref()
+                .map(|last_commit| last_commit.timestamp);
+            newest_first(timestamp, project)
+        }),
+        SortOrder::Frecency => projects.sort_by(|a, b| {
+            let by_score = match (a.frecency, b.frecency) {
+                (Some(a_score), Some(b_score)) => b_score.total_cmp(&a_score),
+                (a_score, b_score) => b_score.is_some().cmp(&a_score.is_some()),
+            };
+            by_score.then_with(|| a.root.cmp(&b.root))
+        }),
+    }
+
+    if reverse {
+        projects.reverse();
+    }
+}
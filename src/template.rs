@@ -0,0 +1,405 @@
+use crate::{
+    git::{GitOperation, GitStatus},
+    project::Project,
+};
+use std::{
+    env::{current_dir, home_dir},
+    ffi::OsString,
+    fmt::{self, Display},
+    fs::canonicalize,
+    path::{Component, Path, PathBuf, absolute},
+    str::FromStr,
+};
+
+/// Fields of a project that can be used in a template.
+const FIELDS: [&str; 32] = [
+    "path",
+    "root",
+    "name",
+    "kinds",
+    "markers",
+    "workspace_parent",
+    "vcs_root",
+    "vcs",
+    "git_root",
+    "bare",
+    "main_worktree",
+    "worktrees",
+    "superproject",
+    "submodules",
+    "depth",
+    "package",
+    "version",
+    "description",
+    "authors",
+    "license",
+    "branch",
+    "head",
+    "operation",
+    "remote",
+    "status",
+    "dirty",
+    "staged",
+    "modified",
+    "untracked",
+    "stashes",
+    "ahead",
+    "behind",
+];
+
+/// Fields that require reading the working tree status.
+pub const STATUS_FIELDS: [&str; 8] = [
+    "status",
+    "dirty",
+    "staged",
+    "modified",
+    "untracked",
+    "stashes",
+    "ahead",
+    "behind",
+];
+
+/// How a path field is rendered.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PathStyle {
+    /// As found during the search
+    #[default]
+    AsFound,
+    /// Relative to the current directory
+    Relative,
+    /// Absolute, with the home directory abbreviated to `~`
+    Tilde,
+    /// Absolute
+    Absolute,
+}
+
+impl FromStr for PathStyle {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "rel" | "relative" => Ok(Self::Relative),
+            "tilde" | "home" => Ok(Self::Tilde),
+            "abs" | "absolute" => Ok(Self::Absolute),
+            _ => Err(format!(
+                "unknown path modifier '{s}' (expected one of: rel, tilde, abs)"
+            )),
+        }
+    }
+}
+
+impl PathStyle {
+    /// Render a path in this style, keeping it byte-exact.
+    pub fn render(self, path: &Path) -> PathBuf {
+        match self {
+            Self::AsFound => path.to_path_buf(),
+            Self::Relative => relative_to_cwd(path),
+            Self::Tilde => {
+                let path = absolute_path(path);
+                let abbreviated = home_dir().and_then(|home| {
+                    let rest = path.strip_prefix(home).ok()?;
+                    let mut abbreviated = PathBuf::from("~");
+                    if !rest.as_os_str().is_empty() {
+                        abbreviated.push(rest);
+                    }
+                    Some(abbreviated)
+                });
+                abbreviated.unwrap_or(path)
+            }
+            Self::Absolute => absolute_path(path),
+        }
+    }
+}
+
+/// Make a path absolute, resolving symlinks and `..` components when the path exists.
+fn absolute_path(path: &Path) -> PathBuf {
+    canonicalize(path)
+        .or_else(|_| absolute(path))
+        .unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// Express `path` relative to the current directory, climbing with `..` if needed.
+fn relative_to_cwd(path: &Path) -> PathBuf {
+    let Ok(cwd) = current_dir() else {
+        return path.to_path_buf();
+    };
+    let path = absolute_path(path);
+
+    let path_components = path.components().collect::<Vec<_>>();
+    let cwd_components = cwd.components().collect::<Vec<_>>();
+    let common = path_components
+        .iter()
+        .zip(&cwd_components)
+        .take_while(|(a, b)| a == b)
+        .count();
+
+    let mut relative = PathBuf::new();
+    for _ in common..cwd_components.len() {
+        relative.push(Component::ParentDir);
+    }
+    relative.extend(&path_components[common..]);
+
+    if relative.as_os_str().is_empty() {
+        PathBuf::from(".")
+    } else {
+        relative
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Segment {
+    Literal(String),
+    Field { name: String, style: PathStyle },
+}
+
+/// A user-defined output template such as `{name}\t{path:tilde}`.
+///
+/// Fields are written as `{field}` or `{field:modifier}`, where the modifier selects how
+/// path fields are rendered. `{{` and `}}` produce literal braces, and `\t`, `\n` and
+/// `\\` are unescaped so templates can be passed in single quotes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Template {
+    segments: Vec<Segment>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TemplateError(pub String);
+
+impl Display for TemplateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for TemplateError {}
+
+impl FromStr for Template {
+    type Err = TemplateError;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let mut segments = Vec::new();
+        let mut literal = String::new();
+        let mut chars = s.chars().peekable();
+
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    literal.push('{');
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    literal.push('}');
+                }
+                '}' => return Err(TemplateError("unmatched '}'".into())),
+                '\\' => match chars.next() {
+                    Some('t') => literal.push('\t'),
+                    Some('n') => literal.push('\n'),
+                    Some('0') => literal.push('\0'),
+                    Some('\\') | None => literal.push('\\'),
+                    Some(other) => {
+                        literal.push('\\');
+                        literal.push(other);
+                    }
+                },
+                '{' => {
+                    let mut field = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some(c) => field.push(c),
+                            None => return Err(TemplateError("unclosed '{'".into())),
+                        }
+                    }
+
+                    if !literal.is_empty() {
+                        segments.push(Segment::Literal(std::mem::take(&mut literal)));
+                    }
+                    segments.push(parse_field(&field)?);
+                }
+                c => literal.push(c),
+            }
+        }
+
+        if !literal.is_empty() {
+            segments.push(Segment::Literal(literal));
+        }
+
+        Ok(Self { segments })
+    }
+}
+
+fn parse_field(field: &str) -> std::result::Result<Segment, TemplateError> {
+    let (name, modifier) = match field.split_once(':') {
+        Some((name, modifier)) => (name.trim(), Some(modifier.trim())),
+        None => (field.trim(), None),
+    };
+
+    if !FIELDS.contains(&name) {
+        return Err(TemplateError(format!(
+            "unknown field '{name}' (expected one of: {})",
+            FIELDS.join(", ")
+        )));
+    }
+
+    let style = match modifier {
+        Some(modifier) if is_path_field(name) => modifier.parse().map_err(TemplateError)?,
+        Some(modifier) => {
+            return Err(TemplateError(format!(
+                "field '{name}' does not accept the modifier '{modifier}'"
+            )));
+        }
+        None => PathStyle::default(),
+    };
+
+    Ok(Segment::Field {
+        name: name.to_string(),
+        style,
+    })
+}
+
+fn is_path_field(name: &str) -> bool {
+    matches!(
+        name,
+        "path"
+            | "root"
+            | "markers"
+            | "workspace_parent"
+            | "vcs_root"
+            | "git_root"
+            | "main_worktree"
+            | "worktrees"
+            | "superproject"
+            | "submodules"
+    )
+}
+
+impl Template {
+    /// Check whether the template references at least one field.
+    pub fn has_fields(&self) -> bool {
+        self.segments
+            .iter()
+            .any(|segment| matches!(segment, Segment::Field { .. }))
+    }
+
+    /// Check whether the template references any of the given fields.
+    pub fn uses_any(&self, fields: &[&str]) -> bool {
+        self.segments.iter().any(|segment| {
+            matches!(segment, Segment::Field { name, .. } if fields.contains(&name.as_str()))
+        })
+    }
+
+    /// Render the template for a single project.
+    ///
+    /// The result is an `OsString` so paths are emitted byte-exact, even when they are
+    /// not valid UTF-8.
+    pub fn render(&self, project: &Project) -> OsString {
+        let mut output = OsString::new();
+        for segment in &self.segments {
+            match segment {
+                Segment::Literal(literal) => output.push(literal),
+                Segment::Field { name, style } => {
+                    output.push(render_field(project, name, *style));
+                }
+            }
+        }
+        output
+    }
+}
+
+/// Render a list of paths separated by commas.
+fn render_paths<'a>(paths: impl IntoIterator<Item = &'a PathBuf>, style: PathStyle) -> OsString {
+    let mut rendered = OsString::new();
+    for (i, path) in paths.into_iter().enumerate() {
+        if i > 0 {
+            rendered.push(",");
+        }
+        rendered.push(style.render(path));
+    }
+    rendered
+}
+
+fn render_field(project: &Project, name: &str, style: PathStyle) -> OsString {
+    let git = project.git.as_ref();
+    let status = project.status.as_ref();
+    let count = |count: fn(&GitStatus) -> Option<usize>| {
+        status
+            .and_then(count)
+            .map(|count| count.to_string())
+            .unwrap_or_default()
+            .into()
+    };
+    let render_path = |path: Option<&Path>| {
+        path.map(|path| style.render(path).into_os_string())
+            .unwrap_or_default()
+    };
+
+    match name {
+        "path" | "root" => style.render(&project.root).into_os_string(),
+        "name" => project.name(),
+        "kinds" => project
+            .kinds
+            .iter()
+            .map(|kind| kind.as_str())
+            .collect::<Vec<_>>()
+            .join(",")
+            .into(),
+        "markers" => render_paths(&project.markers, style),
+        "workspace_parent" => render_path(project.workspace_parent.as_deref()),
+        "vcs_root" => render_path(project.vcs_root.as_deref()),
+        "vcs" => project
+            .vcs
+            .iter()
+            .map(|vcs| vcs.as_str())
+            .collect::<Vec<_>>()
+            .join(",")
+            .into(),
+        "git_root" => render_path(project.git_root.as_deref()),
+        "bare" => if project.bare { "bare" } else { "" }.into(),
+        "main_worktree" => render_path(project.main_worktree.as_deref()),
+        "worktrees" => render_paths(&project.worktrees, style),
+        "superproject" => render_path(project.superproject.as_deref()),
+        "submodules" => render_paths(&project.submodules, style),
+        "depth" => project.depth.to_string().into(),
+        "package" => project.metadata.name.clone().unwrap_or_default().into(),
+        "version" => project.metadata.version.clone().unwrap_or_default().into(),
+        "description" => project
+            .metadata
+            .description
+            .clone()
+            .unwrap_or_default()
+            .into(),
+        "authors" => project.metadata.authors.join(", ").into(),
+        "license" => project.metadata.license.clone().unwrap_or_default().into(),
+        "branch" => git
+            .and_then(|git| git.branch.clone())
+            .unwrap_or_default()
+            .into(),
+        "head" => git
+            .and_then(|git| git.head.clone())
+            .unwrap_or_default()
+            .into(),
+        "operation" => git
+            .and_then(|git| git.operation)
+            .map(GitOperation::as_str)
+            .unwrap_or_default()
+            .into(),
+        "remote" => project
+            .remote()
+            .and_then(|remote| remote.normalized.clone())
+            .unwrap_or_default()
+            .into(),
+        "status" => status.map(ToString::to_string).unwrap_or_default().into(),
+        "dirty" => status
+            .map(|status| if status.dirty { "dirty" } else { "clean" })
+            .unwrap_or_default()
+            .into(),
+        "staged" => count(|status| Some(status.staged)),
+        "modified" => count(|status| Some(status.modified)),
+        "untracked" => count(|status| Some(status.untracked)),
+        "stashes" => count(|status| Some(status.stashes)),
+        "ahead" => count(|status| status.ahead),
+        "behind" => count(|status| status.behind),
+        _ => OsString::new(),
+    }
+}
@@ -0,0 +1,197 @@
+use crate::{
+    output::write_os_str,
+    project::{Project, ProjectKind},
+    vcs::{self, Vcs},
+};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+/// The kind of repository a node is the root of.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Repo {
+    Vcs(Vcs),
+    /// A linked Git worktree of another repository.
+    Worktree,
+    /// A Git submodule of an enclosing repository.
+    Submodule,
+    Bare,
+}
+
+impl Repo {
+    /// Classify the repository rooted at a project root.
+    fn of(project: &Project) -> Option<Self> {
+        if project.vcs_root.as_ref() != Some(&project.root) {
+            None
+        } else if project.bare {
+            Some(Self::Bare)
+        } else if project.main_worktree.is_some() {
+            Some(Self::Worktree)
+        } else if project.superproject.is_some() {
+            Some(Self::Submodule)
+        } else {
+            project.vcs.first().copied().map(Self::Vcs)
+        }
+    }
+
+    /// Classify the repository rooted at a directory that is not a project root.
+    async fn detect(dir: &Path) -> Option<Self> {
+        vcs::detect(dir).await.first().copied().map(Self::Vcs)
+    }
+
+    const fn as_str(self) -> &'static str {
+        match self {
+            Self::Vcs(vcs) => vcs.as_str(),
+            Self::Worktree => "worktree",
+            Self::Submodule => "submodule",
+            Self::Bare => "bare",
+        }
+    }
+}
+
+/// A directory shown in the tree: a project root, a workspace member or a sub-project.
+#[derive(Debug, Default)]
+struct Node {
+    kinds: BTreeSet<ProjectKind>,
+    repo: Option<Repo>,
+    children: Vec<PathBuf>,
+}
+
+/// Collect the directories to display, keyed by path.
+///
+/// Besides the project roots themselves, every directory holding a marker file that was
+/// collapsed into a root and every submodule of a root becomes a node of its own.
+async fn collect_nodes(projects: &[Project]) -> BTreeMap<PathBuf, Node> {
+    let mut nodes = BTreeMap::<PathBuf, Node>::new();
+
+    for project in projects {
+        let node = nodes.entry(project.root.clone()).or_default();
+        node.kinds.extend(&project.kinds);
+        node.repo = Repo::of(project);
+
+        if project.git_root.as_ref() == Some(&project.root) {
+            for submodule in &project.submodules {
+                nodes.entry(submodule.clone()).or_default().repo = Some(Repo::Submodule);
+            }
+        }
+
+        for member in &project.members {
+            let (Some(dir), Some(name)) = (member.parent(), member.file_name()) else {
+                continue;
+            };
+
+            if !nodes.contains_key(dir) {
+                let node = Node {
+                    repo: Repo::detect(dir).await,
+                    ..Node::default()
+                };
+                nodes.insert(dir.to_path_buf(), node);
+            }
+            if let Some(node) = nodes.get_mut(dir) {
+                node.kinds
+                    .extend(name.to_str().and_then(ProjectKind::from_marker));
+            }
+        }
+    }
+
+    nodes
+}
+
+/// Print projects as a tree showing workspace members, nested repositories,
+/// submodules, sub-projects and grouped worktrees below each root.
+///
+/// # Errors
+///
+/// Returns an error if writing to stdout fails.
+pub async fn print_tree(projects: &[Project]) -> io::Result<()> {
+    let mut nodes = collect_nodes(projects).await;
+
+    // Paths are ordered component-wise, so every ancestor is visited before its
+    // descendants and the nearest one is on top of the stack.
+    let mut top_level = Vec::new();
+    let mut stack = Vec::<PathBuf>::new();
+    let paths = nodes.keys().cloned().collect::<Vec<_>>();
+    for path in paths {
+        while stack.last().is_some_and(|parent| !path.starts_with(parent)) {
+            stack.pop();
+        }
+
+        match stack.last().and_then(|parent| nodes.get_mut(parent)) {
+            Some(parent) => parent.children.push(path.clone()),
+            None => top_level.push(path.clone()),
+        }
+        stack.push(path);
+    }
+
+    // Worktrees grouped into their main repository are listed below it.
+    for project in projects {
+        if project.git_root.as_ref() != Some(&project.root) {
+            continue;
+        }
+        for worktree in &project.worktrees {
+            if nodes.contains_key(worktree) {
+                continue;
+            }
+            nodes.insert(
+                worktree.clone(),
+                Node {
+                    repo: Some(Repo::Worktree),
+                    ..Node::default()
+                },
+            );
+            if let Some(main) = nodes.get_mut(&project.root) {
+                main.children.push(worktree.clone());
+            }
+        }
+    }
+
+    let mut stdout = io::stdout().lock();
+    for path in &top_level {
+        write_node(&mut stdout, &nodes, path, path, "")?;
+    }
+
+    stdout.flush()
+}
+
+fn write_node(
+    out: &mut impl Write,
+    nodes: &BTreeMap<PathBuf, Node>,
+    path: &Path,
+    label: &Path,
+    prefix: &str,
+) -> io::Result<()> {
+    let Some(node) = nodes.get(path) else {
+        return Ok(());
+    };
+
+    write_os_str(out, label.as_os_str())?;
+    if !node.kinds.is_empty() {
+        let kinds = node
+            .kinds
+            .iter()
+            .map(|kind| kind.as_str())
+            .collect::<Vec<_>>();
+        write!(out, " [{}]", kinds.join(", "))?;
+    }
+    if let Some(repo) = node.repo {
+        write!(out, " ({})", repo.as_str())?;
+    }
+    writeln!(out)?;
+
+    for (i, child) in node.children.iter().enumerate() {
+        let last = i + 1 == node.children.len();
+        let (branch, indent) = if last {
+            ("└── ", "    ")
+        } else {
+            ("├── ", "│   ")
+        };
+
+        write!(out, "{prefix}{branch}")?;
+        let label = child.strip_prefix(path).unwrap_or(child);
+        write_node(out, nodes, child, label, &format!("{prefix}{indent}"))?;
+    }
+
+    Ok(())
+}
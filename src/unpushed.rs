@@ -0,0 +1,25 @@
+use crate::{errors::Result, finder::ProjectFinder, project::Project};
+
+/// Find the Git repositories with work that only exists locally.
+///
+/// Every repository containing a discovered project is inspected once, linked worktrees
+/// through their main working tree. A repository is reported if it has no remote at
+/// all, or if any of its local branches has commits that are not on a remote-tracking
+/// branch.
+///
+/// # Returns
+///
+/// The repository roots as projects, sorted by root.
+pub async fn find_unpushed(finder: &ProjectFinder) -> Result<Vec<Project>> {
+    let mut projects = finder.find_repositories().await?;
+
+    finder.read_unpushed(&mut projects).await?;
+    projects.retain(|project| {
+        project
+            .unpushed
+            .as_ref()
+            .is_some_and(|unpushed| !unpushed.is_empty())
+    });
+
+    Ok(projects)
+}
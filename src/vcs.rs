@@ -0,0 +1,116 @@
+use crate::git::is_repo_root;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeSet,
+    fmt::{self, Display},
+    path::Path,
+};
+use tokio::fs::metadata;
+
+/// A version control system whose working copies are recognized as repository roots.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Vcs {
+    Git,
+    Jujutsu,
+    Mercurial,
+    Fossil,
+    Subversion,
+    Pijul,
+}
+
+impl Vcs {
+    pub const ALL: [Self; 6] = [
+        Self::Git,
+        Self::Jujutsu,
+        Self::Mercurial,
+        Self::Fossil,
+        Self::Subversion,
+        Self::Pijul,
+    ];
+
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Git => "git",
+            Self::Jujutsu => "jujutsu",
+            Self::Mercurial => "mercurial",
+            Self::Fossil => "fossil",
+            Self::Subversion => "subversion",
+            Self::Pijul => "pijul",
+        }
+    }
+
+    /// Names of the entries marking the root of a working copy.
+    pub const fn markers(self) -> &'static [&'static str] {
+        match self {
+            Self::Git => &[".git"],
+            Self::Jujutsu => &[".jj"],
+            Self::Mercurial => &[".hg"],
+            Self::Fossil => &[".fslckout", "_FOSSIL_"],
+            Self::Subversion => &[".svn"],
+            Self::Pijul => &[".pijul"],
+        }
+    }
+
+    /// Check whether `dir` is the root of a working copy of this system.
+    pub async fn is_root(self, dir: &Path) -> bool {
+        match self {
+            Self::Git => is_repo_root(dir).await,
+            // Subversion before 1.7 keeps a `.svn` directory in every directory of the
+            // working copy, only the topmost one is its root.
+            Self::Subversion => {
+                self.has_marker(dir).await
+                    && match dir.parent() {
+                        Some(parent) => !self.has_marker(parent).await,
+                        None => true,
+                    }
+            }
+            _ => self.has_marker(dir).await,
+        }
+    }
+
+    /// Check whether `dir` contains one of the entries marking a working copy.
+    async fn has_marker(self, dir: &Path) -> bool {
+        for marker in self.markers() {
+            if metadata(dir.join(marker)).await.is_ok() {
+                return true;
+            }
+        }
+        false
+    }
+}
+
+impl Display for Vcs {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Names of the entries marking the root of a working copy of any supported system.
+pub fn marker_names() -> impl Iterator<Item = &'static str> {
+    Vcs::ALL.into_iter().flat_map(Vcs::markers).copied()
+}
+
+/// Detect the version control systems with a working copy rooted at `dir`.
+///
+/// More than one system is returned for colocated repositories, such as a Jujutsu
+/// repository backed by Git.
+pub async fn detect(dir: &Path) -> BTreeSet<Vcs> {
+    let mut found = BTreeSet::new();
+    for vcs in Vcs::ALL {
+        if vcs.is_root(dir).await {
+            found.insert(vcs);
+        }
+    }
+    found
+}
+
+/// Check whether `dir` is the root of a working copy of any supported system.
+pub async fn is_vcs_root(dir: &Path) -> bool {
+    for vcs in Vcs::ALL {
+        if vcs.is_root(dir).await {
+            return true;
+        }
+    }
+    false
+}